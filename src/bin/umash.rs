@@ -0,0 +1,109 @@
+//! Command-line tools built on UMASH fingerprints.
+//!
+//! ```text
+//! umash dupes [--json] <paths...>
+//! ```
+const USAGE: &str = "usage: umash dupes [--json] <paths...>";
+
+/// Returns `value` as a JSON string literal.
+fn json_string(value: &str) -> String {
+    let mut ret = String::with_capacity(value.len() + 2);
+
+    ret.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => ret.push_str("\\\""),
+            '\\' => ret.push_str("\\\\"),
+            '\n' => ret.push_str("\\n"),
+            '\r' => ret.push_str("\\r"),
+            '\t' => ret.push_str("\\t"),
+            c if (c as u32) < 0x20 => ret.push_str(&format!("\\u{:04x}", c as u32)),
+            c => ret.push(c),
+        }
+    }
+    ret.push('"');
+    ret
+}
+
+fn dupes(args: &[String]) -> Result<(), String> {
+    let mut json = false;
+    let mut roots = Vec::new();
+
+    for arg in args {
+        match arg.as_str() {
+            "--json" => json = true,
+            flag if flag.starts_with("--") => return Err(format!("unknown flag {}", flag)),
+            path => roots.push(path),
+        }
+    }
+
+    if roots.is_empty() {
+        return Err(USAGE.to_string());
+    }
+
+    // Duplicates only have to be consistent within this run, so we can
+    // use fresh secret parameters.
+    let params = umash::Params::new();
+    let duplicates = umash::dupes::find_duplicates(&params, roots);
+    let groups = &duplicates.groups;
+
+    if json {
+        let groups: Vec<String> = groups
+            .iter()
+            .map(|group| {
+                let paths: Vec<String> = group
+                    .paths
+                    .iter()
+                    .map(|path| json_string(&path.to_string_lossy()))
+                    .collect();
+
+                format!(
+                    "{{\"size\":{},\"fingerprint\":\"{}\",\"paths\":[{}]}}",
+                    group.len,
+                    group.fingerprint,
+                    paths.join(",")
+                )
+            })
+            .collect();
+
+        println!("[{}]", groups.join(","));
+    } else {
+        for (i, group) in groups.iter().enumerate() {
+            if i > 0 {
+                println!();
+            }
+
+            println!("{} bytes, fingerprint {}", group.len, group.fingerprint);
+            for path in group.paths.iter() {
+                println!("{}", path.display());
+            }
+        }
+    }
+
+    // Report unreadable paths after the results, and fail once done.
+    for (path, error) in duplicates.errors.iter() {
+        eprintln!("umash: {}: {}", path.display(), error);
+    }
+
+    if !duplicates.errors.is_empty() {
+        return Err(format!(
+            "could not read {} path(s)",
+            duplicates.errors.len()
+        ));
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("dupes") => dupes(&args[1..]),
+        _ => Err(USAGE.to_string()),
+    };
+
+    if let Err(message) = result {
+        eprintln!("umash: {}", message);
+        std::process::exit(1);
+    }
+}
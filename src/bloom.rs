@@ -0,0 +1,446 @@
+//! A Bloom filter keyed by UMASH [`Fingerprint`]s.
+//!
+//! Each item is fingerprinted once, and the filter derives its `k`
+//! bit indices from the primary and secondary hash values in the
+//! [`Fingerprint`] with double hashing: the `i`th index is
+//! `hash + i * secondary`, reduced to the filter's size.  See Kirsch
+//! and Mitzenmacher's "Less Hashing, Same Performance: Building a
+//! Better Bloom Filter" for why that's as good as `k` independent
+//! hash functions.
+//!
+//! When a filter is serialised and queried by another program,
+//! fingerprint the raw bytes with a [`Fingerprinter`](crate::Fingerprinter)
+//! and call [`BloomFilter::insert_fingerprint`] and
+//! [`BloomFilter::contains_fingerprint`], rather than going through
+//! [`std::hash::Hash`] (see the [crate docs](crate#data-structures)).
+use crate::codec::{check_params, Decoder, Encoder};
+use crate::range::hash_to_range;
+use crate::{DecodeError, Fingerprint, MergeError, Params};
+use std::borrow::Borrow;
+
+const MAGIC: &[u8; 4] = b"UMBF";
+const VERSION: u8 = 1;
+
+/// A [`BloomFilter`] is a probabilistic set: it may report false
+/// positives, but never false negatives.
+///
+/// See the [crate docs](crate#data-structures) for the choice of
+/// `P`.
+#[derive(Clone)]
+pub struct BloomFilter<P: Borrow<Params>> {
+    params: P,
+    bits: Vec<u64>,
+    num_bits: u64,
+    num_hashes: u32,
+}
+
+/// Returns the number of bits and the number of hash functions that
+/// minimise the size of a Bloom filter for `expected_items`, with a
+/// false positive rate of at most `fpr`.
+///
+/// # Panics
+///
+/// Panics if `fpr` is not strictly between 0 and 1.
+pub fn optimal_parameters(expected_items: usize, fpr: f64) -> (u64, u32) {
+    assert!(
+        fpr > 0.0 && fpr < 1.0,
+        "false positive rate must be in (0, 1)"
+    );
+
+    let n = expected_items.max(1) as f64;
+    let ln2 = std::f64::consts::LN_2;
+    let num_bits = (-n * fpr.ln() / (ln2 * ln2)).ceil().max(1.0);
+    let num_hashes = (num_bits / n * ln2).round().max(1.0);
+
+    (num_bits as u64, num_hashes as u32)
+}
+
+impl<P: Borrow<Params>> BloomFilter<P> {
+    /// Returns an empty [`BloomFilter`] with `num_bits` bits, that
+    /// sets `num_hashes` bits for each item.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_bits` or `num_hashes` is zero.
+    pub fn new(params: P, num_bits: u64, num_hashes: u32) -> Self {
+        assert!(num_bits > 0, "Bloom filters must have at least one bit");
+        assert!(num_hashes > 0, "Bloom filters need at least one hash");
+
+        BloomFilter {
+            params,
+            bits: vec![0; ((num_bits - 1) / 64 + 1) as usize],
+            num_bits,
+            num_hashes,
+        }
+    }
+
+    /// Returns an empty [`BloomFilter`] sized to hold `expected_items`
+    /// with a false positive rate of at most `fpr`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `fpr` is not strictly between 0 and 1.
+    pub fn with_capacity(params: P, expected_items: usize, fpr: f64) -> Self {
+        let (num_bits, num_hashes) = optimal_parameters(expected_items, fpr);
+
+        Self::new(params, num_bits, num_hashes)
+    }
+
+    /// Returns the [`Params`] for this filter.
+    #[inline(always)]
+    pub fn params(&self) -> &Params {
+        self.params.borrow()
+    }
+
+    /// Returns the number of bits in the filter.
+    #[inline(always)]
+    pub fn num_bits(&self) -> u64 {
+        self.num_bits
+    }
+
+    /// Returns the number of bits set for each item.
+    #[inline(always)]
+    pub fn num_hashes(&self) -> u32 {
+        self.num_hashes
+    }
+
+    #[inline(always)]
+    fn indices(&self, fprint: Fingerprint) -> impl Iterator<Item = u64> {
+        let (hash, secondary) = (fprint.hash(), fprint.secondary());
+        let num_bits = self.num_bits;
+
+        (0..self.num_hashes as u64)
+            .map(move |i| hash_to_range(hash.wrapping_add(i.wrapping_mul(secondary)), num_bits))
+    }
+
+    /// Adds `item` to the filter.
+    ///
+    /// Returns false if the filter probably already contained `item`.
+    pub fn insert<T: std::hash::Hash + ?Sized>(&mut self, item: &T) -> bool {
+        let fprint = self.params().fingerprint(item);
+
+        self.insert_fingerprint(fprint)
+    }
+
+    /// Adds the item with [`Fingerprint`] `fprint` to the filter.  The
+    /// fingerprint must have been computed with this filter's
+    /// [`Params`].
+    ///
+    /// Returns false if the filter probably already contained the item.
+    pub fn insert_fingerprint(&mut self, fprint: Fingerprint) -> bool {
+        let mut inserted = false;
+
+        for index in self.indices(fprint) {
+            let word = &mut self.bits[(index / 64) as usize];
+            let mask = 1u64 << (index % 64);
+
+            inserted |= (*word & mask) == 0;
+            *word |= mask;
+        }
+
+        inserted
+    }
+
+    /// Determines whether the filter probably contains `item`.
+    pub fn contains<T: std::hash::Hash + ?Sized>(&self, item: &T) -> bool {
+        self.contains_fingerprint(self.params().fingerprint(item))
+    }
+
+    /// Determines whether the filter probably contains the item with
+    /// [`Fingerprint`] `fprint`.  The fingerprint must have been
+    /// computed with this filter's [`Params`].
+    pub fn contains_fingerprint(&self, fprint: Fingerprint) -> bool {
+        self.indices(fprint)
+            .all(|index| (self.bits[(index / 64) as usize] >> (index % 64)) & 1 != 0)
+    }
+
+    /// Removes all items from the filter.
+    pub fn clear(&mut self) {
+        self.bits.iter_mut().for_each(|word| *word = 0);
+    }
+
+    /// Determines whether the filter is empty.
+    pub fn is_empty(&self) -> bool {
+        self.bits.iter().all(|word| *word == 0)
+    }
+
+    /// Returns the number of bits set in the filter.
+    pub fn count_ones(&self) -> u64 {
+        self.bits.iter().map(|word| word.count_ones() as u64).sum()
+    }
+
+    /// Estimates the number of distinct items in the filter, from the
+    /// fraction of bits set.
+    pub fn estimated_len(&self) -> f64 {
+        let m = self.num_bits as f64;
+        let k = self.num_hashes as f64;
+        let ones = self.count_ones() as f64;
+
+        if ones >= m {
+            return f64::INFINITY;
+        }
+
+        -(m / k) * (1.0 - ones / m).ln()
+    }
+
+    /// Estimates the current false positive rate of the filter, from
+    /// the fraction of bits set.
+    pub fn estimated_fpr(&self) -> f64 {
+        (self.count_ones() as f64 / self.num_bits as f64).powi(self.num_hashes as i32)
+    }
+
+    fn check_compatible<Q: Borrow<Params>>(
+        &self,
+        other: &BloomFilter<Q>,
+    ) -> Result<(), MergeError> {
+        check_params(self.params().id(), other.params().id())?;
+        if self.num_bits != other.num_bits || self.num_hashes != other.num_hashes {
+            return Err(MergeError::ShapeMismatch);
+        }
+
+        Ok(())
+    }
+
+    /// Adds all the items in `other` to this filter.  The result is the
+    /// same as if all the items in `other` had been inserted in `self`.
+    ///
+    /// Fails without modifying `self` if the two filters have
+    /// different [`Params`] or sizes.
+    pub fn union<Q: Borrow<Params>>(&mut self, other: &BloomFilter<Q>) -> Result<(), MergeError> {
+        self.check_compatible(other)?;
+        for (dst, src) in self.bits.iter_mut().zip(other.bits.iter()) {
+            *dst |= *src;
+        }
+
+        Ok(())
+    }
+
+    /// Only keeps the bits that are also set in `other`.  The result
+    /// contains (at least) all the items in both filters, with a false
+    /// positive rate that may be higher than that of a filter built
+    /// from the intersection directly.
+    ///
+    /// Fails without modifying `self` if the two filters have
+    /// different [`Params`] or sizes.
+    pub fn intersection<Q: Borrow<Params>>(
+        &mut self,
+        other: &BloomFilter<Q>,
+    ) -> Result<(), MergeError> {
+        self.check_compatible(other)?;
+        for (dst, src) in self.bits.iter_mut().zip(other.bits.iter()) {
+            *dst &= *src;
+        }
+
+        Ok(())
+    }
+
+    /// Serialises the filter to bytes, including the [`ParamsId`](crate::ParamsId)
+    /// of the filter's [`Params`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut encoder = Encoder::new(MAGIC, VERSION, self.params().id());
+
+        encoder.u64(self.num_bits).u32(self.num_hashes);
+        for word in &self.bits {
+            encoder.u64(*word);
+        }
+
+        encoder.finish()
+    }
+
+    /// Deserialises a filter serialised by [`BloomFilter::to_bytes`].
+    ///
+    /// Fails if `params` is not the [`Params`] that built the filter.
+    pub fn from_bytes(params: P, bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut decoder = Decoder::new(bytes, MAGIC, VERSION, params.borrow().id())?;
+        let num_bits = decoder.u64()?;
+        let num_hashes = decoder.u32()?;
+
+        if num_bits == 0 || num_hashes == 0 {
+            return Err(DecodeError::Invalid("empty Bloom filter"));
+        }
+
+        // `num_bits > 0`, so this rounds up without overflowing.
+        let num_words = (num_bits - 1) / 64 + 1;
+        if decoder.remaining() as u64 != 8 * num_words {
+            return Err(DecodeError::Invalid("Bloom filter size mismatch"));
+        }
+
+        let bits = (0..num_words)
+            .map(|_| decoder.u64())
+            .collect::<Result<Vec<_>, _>>()?;
+        decoder.finish()?;
+
+        // Bits past `num_bits` are never set.
+        if num_bits % 64 != 0 && bits[bits.len() - 1] >> (num_bits % 64) != 0 {
+            return Err(DecodeError::Invalid("bits set past the end of the filter"));
+        }
+
+        Ok(BloomFilter {
+            params,
+            bits,
+            num_bits,
+            num_hashes,
+        })
+    }
+}
+
+/// See the [crate docs](crate#data-structures) for the
+/// [`Debug`](std::fmt::Debug) representation of data structures.
+impl<P: Borrow<Params>> std::fmt::Debug for BloomFilter<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BloomFilter")
+            .field("params", &self.params().id())
+            .field("num_bits", &self.num_bits)
+            .field("num_hashes", &self.num_hashes)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bloom::{optimal_parameters, BloomFilter};
+    use crate::{DecodeError, MergeError, Params};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_optimal_parameters() {
+        assert_eq!(optimal_parameters(1000, 0.01), (9586, 7));
+        assert_eq!(optimal_parameters(0, 0.5), (2, 1));
+    }
+
+    #[test]
+    fn test_no_false_negatives() {
+        let params = Params::derive(0, b"bloom");
+        let mut filter = BloomFilter::with_capacity(&params, 10_000, 0.01);
+
+        assert!(filter.is_empty());
+        let already_present = (0..10_000u64).filter(|i| !filter.insert(i)).count();
+        assert!(already_present < 100, "{} false positives", already_present);
+
+        assert!(!filter.insert(&0u64));
+        assert!((0..10_000u64).all(|i| filter.contains(&i)));
+
+        let false_positives = (10_000..110_000u64).filter(|i| filter.contains(i)).count();
+        assert!(
+            false_positives < 1500,
+            "{} false positives",
+            false_positives
+        );
+        assert!((filter.estimated_len() - 10_000.0).abs() < 500.0);
+        assert!(filter.estimated_fpr() < 0.015);
+
+        filter.clear();
+        assert!(filter.is_empty());
+        assert!(!filter.contains(&0u64));
+    }
+
+    #[test]
+    fn test_fingerprints() {
+        let params = Params::derive(0, b"bloom");
+        let mut filter = BloomFilter::with_capacity(params.clone(), 1000, 0.01);
+        let fprint = |i: u32| {
+            params
+                .fingerprinter(0)
+                .write(format!("key {}", i).as_bytes())
+                .digest()
+        };
+
+        for i in 0..1000 {
+            filter.insert_fingerprint(fprint(i));
+        }
+
+        // No false negatives, and about 1% false positives.
+        assert!((0..1000).all(|i| filter.contains_fingerprint(fprint(i))));
+        let false_positives = (1000..11_000)
+            .filter(|i| filter.contains_fingerprint(fprint(*i)))
+            .count();
+        assert!(false_positives < 150, "{} false positives", false_positives);
+
+        // `insert` goes through `Params::fingerprint`.
+        assert!(!filter.contains_fingerprint(params.fingerprint("bar")));
+        filter.insert("bar");
+        assert!(filter.contains_fingerprint(params.fingerprint("bar")));
+    }
+
+    #[test]
+    fn test_union_intersection() {
+        let params = Arc::new(Params::derive(0, b"bloom"));
+        let mut left = BloomFilter::with_capacity(params.clone(), 1000, 0.001);
+        let mut right = BloomFilter::with_capacity(params.clone(), 1000, 0.001);
+
+        for i in 0..100u64 {
+            left.insert(&i);
+            right.insert(&(i + 50));
+        }
+
+        let mut both = left.clone();
+        both.intersection(&right).expect("compatible filters");
+        assert!((50..100u64).all(|i| both.contains(&i)));
+        assert!((0..50u64).filter(|i| both.contains(i)).count() < 5);
+
+        left.union(&right).expect("compatible filters");
+        assert!((0..150u64).all(|i| left.contains(&i)));
+
+        let other_params = Params::derive(1, b"bloom");
+        assert_eq!(
+            left.union(&BloomFilter::with_capacity(&other_params, 1000, 0.001)),
+            Err(MergeError::ParamsMismatch {
+                left: params.id(),
+                right: other_params.id()
+            })
+        );
+        assert_eq!(
+            left.union(&BloomFilter::with_capacity(&*params, 2000, 0.001)),
+            Err(MergeError::ShapeMismatch)
+        );
+    }
+
+    #[test]
+    fn test_serialization() {
+        let params = Params::derive(0, b"bloom");
+        let mut filter = BloomFilter::new(&params, 1000, 5);
+
+        for i in 0..100u64 {
+            filter.insert(&i);
+        }
+
+        let bytes = filter.to_bytes();
+        assert_eq!(bytes.len(), 4 + 1 + 8 + 8 + 4 + 16 * 8);
+
+        let copy = BloomFilter::from_bytes(&params, &bytes).expect("valid encoding");
+        assert_eq!(copy.num_bits(), 1000);
+        assert_eq!(copy.num_hashes(), 5);
+        assert_eq!(copy.count_ones(), filter.count_ones());
+        assert!((0..100u64).all(|i| copy.contains(&i)));
+
+        let other = Params::derive(1, b"bloom");
+        assert_eq!(
+            BloomFilter::from_bytes(&other, &bytes).err(),
+            Some(DecodeError::ParamsMismatch {
+                expected: other.id(),
+                found: params.id()
+            })
+        );
+        assert_eq!(
+            BloomFilter::from_bytes(&params, &bytes[..bytes.len() - 1]).err(),
+            Some(DecodeError::Invalid("Bloom filter size mismatch"))
+        );
+
+        // A huge bit count must not overflow when rounding up to words.
+        let mut huge = bytes[..4 + 1 + 8].to_vec();
+        huge.extend_from_slice(&u64::MAX.to_le_bytes());
+        huge.extend_from_slice(&5u32.to_le_bytes());
+        assert_eq!(
+            BloomFilter::from_bytes(&params, &huge).err(),
+            Some(DecodeError::Invalid("Bloom filter size mismatch"))
+        );
+        assert_eq!(
+            BloomFilter::from_bytes(&params, &bytes[..10]).err(),
+            Some(DecodeError::Truncated)
+        );
+        assert_eq!(
+            BloomFilter::from_bytes(&params, b"UMHL\x01").err(),
+            Some(DecodeError::BadMagic)
+        );
+    }
+}
@@ -0,0 +1,526 @@
+//! A content-addressed blob store on the local filesystem, keyed by
+//! UMASH [`Fingerprint`]s.
+//!
+//! A [`BlobStore`] stores each blob in a file named after the hex
+//! representation of its [`Fingerprint`] (with `seed = 0`), under two
+//! levels of shard directories named after the first four hex digits:
+//! the blob with fingerprint `0123abcd...` lives in
+//! `root/01/23/0123abcd...`.  The root directory also records the
+//! [`ParamsId`] of the store's [`Params`], so a store can't
+//! accidentally be reopened with different [`Params`].
+//!
+//! Blobs are first written to a temporary file in `root/tmp`, then
+//! hard linked into place, so readers never observe partial blobs, and
+//! concurrent writers never replace each other's blobs: whoever links
+//! second compares its bytes with the published blob.  On filesystems
+//! without hard links (e.g., FAT or some network mounts), the store
+//! falls back to checking for an existing blob, then renaming the
+//! temporary file into place; readers still never observe partial
+//! blobs, but a concurrent writer of a colliding blob may replace the
+//! other one.  Reads
+//! re-fingerprint the blob's contents, and report mismatches as
+//! [`CasError::Corrupt`].  Storing different bytes with the same
+//! fingerprint as an existing blob fails with [`CasError::Collision`]
+//! instead of silently aliasing the two.
+//!
+//! UMASH fingerprints are not cryptographic: with [`Params`] known to
+//! attackers (e.g., derived from a public key), they can construct
+//! collisions.  The store detects these collisions, but can't store
+//! both blobs.
+use crate::{Fingerprint, Params, ParamsId};
+use std::borrow::Borrow;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+const PARAMS_FILE: &str = "params";
+const TMP_DIR: &str = "tmp";
+
+// Disambiguates temporary files created by the same process.
+static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// A [`CasError`] describes why a [`BlobStore`] operation failed.
+#[derive(Debug)]
+pub enum CasError {
+    /// An I/O operation failed.
+    Io(std::io::Error),
+
+    /// The store already has a blob with the same [`Fingerprint`] but
+    /// different contents.
+    Collision(Fingerprint),
+
+    /// The stored blob for this [`Fingerprint`] does not match its
+    /// fingerprint.
+    Corrupt(Fingerprint),
+
+    /// The store was created with a different set of [`Params`].
+    ParamsMismatch { expected: ParamsId, found: ParamsId },
+}
+
+impl std::fmt::Display for CasError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CasError::Io(e) => write!(f, "I/O error: {}", e),
+            CasError::Collision(fprint) => {
+                write!(f, "fingerprint collision for blob {}", fprint)
+            }
+            CasError::Corrupt(fprint) => write!(f, "corrupt blob {}", fprint),
+            CasError::ParamsMismatch { expected, found } => write!(
+                f,
+                "store created with params {}, expected params {}",
+                found, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CasError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CasError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for CasError {
+    fn from(e: std::io::Error) -> Self {
+        CasError::Io(e)
+    }
+}
+
+/// A [`BlobStore`] stores immutable blobs in a directory tree, keyed
+/// by their [`Fingerprint`].
+///
+/// See the [crate docs](crate#data-structures) for the choice of
+/// `P`.
+pub struct BlobStore<P: Borrow<Params>> {
+    params: P,
+    root: PathBuf,
+}
+
+/// Removes a temporary file when dropped, unless it was renamed into
+/// place.
+struct TempFile {
+    path: PathBuf,
+    keep: bool,
+}
+
+impl TempFile {
+    /// Returns a fresh path in the temporary directory under `root`.
+    fn new(root: &Path) -> Self {
+        TempFile {
+            path: root.join(TMP_DIR).join(format!(
+                "{}-{}",
+                std::process::id(),
+                TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+            )),
+            keep: false,
+        }
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        if !self.keep {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Publishes `temp` at `path`, unless a file already exists there, and
+/// returns whether `temp` was published.
+///
+/// Hard linking fails atomically if `path` exists.  When that fails
+/// for any other reason (e.g., the filesystem has no hard links),
+/// checks for an existing file, and renames `temp` into place.
+fn publish(temp: &mut TempFile, path: &Path) -> std::io::Result<bool> {
+    match std::fs::hard_link(&temp.path, path) {
+        Ok(()) => return Ok(true),
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => return Ok(false),
+        Err(_) => {}
+    }
+
+    match std::fs::symlink_metadata(path) {
+        Ok(_) => return Ok(false),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+
+    std::fs::rename(&temp.path, path)?;
+    temp.keep = true;
+    Ok(true)
+}
+
+/// Determines whether `left` and `right` have the same contents.
+fn same_contents(left: &Path, right: &Path) -> std::io::Result<bool> {
+    let mut left = File::open(left)?;
+    let mut right = File::open(right)?;
+
+    if left.metadata()?.len() != right.metadata()?.len() {
+        return Ok(false);
+    }
+
+    let mut left_buf = vec![0u8; 64 * 1024];
+    let mut right_buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = left.read(&mut left_buf)?;
+
+        if n == 0 {
+            // Make sure `right` didn't grow in the meantime.
+            return Ok(right.read(&mut right_buf[..1])? == 0);
+        }
+
+        right.read_exact(&mut right_buf[..n])?;
+        if left_buf[..n] != right_buf[..n] {
+            return Ok(false);
+        }
+    }
+}
+
+impl<P: Borrow<Params>> BlobStore<P> {
+    /// Opens the store in directory `root`, and creates it if
+    /// necessary.
+    ///
+    /// Fails with [`CasError::ParamsMismatch`] if the store was
+    /// created with different [`Params`].
+    pub fn open(params: P, root: impl Into<PathBuf>) -> Result<Self, CasError> {
+        let root = root.into();
+        let expected = params.borrow().id();
+
+        std::fs::create_dir_all(root.join(TMP_DIR))?;
+
+        let params_path = root.join(PARAMS_FILE);
+        loop {
+            match std::fs::read_to_string(&params_path) {
+                Ok(contents) => {
+                    let found = u64::from_str_radix(contents.trim(), 16).map_err(|_| {
+                        std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid params file")
+                    })?;
+
+                    if found != expected.0 {
+                        return Err(CasError::ParamsMismatch {
+                            expected,
+                            found: ParamsId(found),
+                        });
+                    }
+
+                    return Ok(BlobStore { params, root });
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    // Write the params file in full before publishing
+                    // it, so concurrent openers never read a partial
+                    // file.  If another opener won the race, loop back
+                    // and check its params.
+                    let mut temp = TempFile::new(&root);
+
+                    std::fs::write(&temp.path, format!("{}\n", expected))?;
+                    if publish(&mut temp, &params_path)? {
+                        return Ok(BlobStore { params, root });
+                    }
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// Returns the [`Params`] for this store.
+    #[inline(always)]
+    pub fn params(&self) -> &Params {
+        self.params.borrow()
+    }
+
+    /// Returns the root directory of the store.
+    #[inline(always)]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Returns the path where the blob with [`Fingerprint`] `fprint`
+    /// is (or would be) stored.
+    pub fn path(&self, fprint: &Fingerprint) -> PathBuf {
+        let name = fprint.to_string();
+
+        self.root.join(&name[0..2]).join(&name[2..4]).join(name)
+    }
+
+    /// Determines whether the store has a blob for `fprint`.
+    pub fn contains(&self, fprint: &Fingerprint) -> bool {
+        self.path(fprint).is_file()
+    }
+
+    /// Stores `bytes`, and returns their [`Fingerprint`].
+    ///
+    /// Storing the same bytes again is a no-op.  Fails with
+    /// [`CasError::Collision`] if a different blob with the same
+    /// fingerprint is already stored.
+    pub fn put(&self, bytes: &[u8]) -> Result<Fingerprint, CasError> {
+        self.put_reader(bytes)
+    }
+
+    /// Stores the bytes read from `reader`, and returns their
+    /// [`Fingerprint`].  The bytes are streamed to disk, and never
+    /// fully buffered in memory.
+    ///
+    /// Storing the same bytes again is a no-op.  Fails with
+    /// [`CasError::Collision`] if a different blob with the same
+    /// fingerprint is already stored.
+    pub fn put_reader<R: Read>(&self, mut reader: R) -> Result<Fingerprint, CasError> {
+        let mut temp = TempFile::new(&self.root);
+
+        let fprint = {
+            let mut file = File::create(&temp.path)?;
+            let mut fingerprinter = self.params().fingerprinter(0);
+            let mut buf = vec![0u8; 64 * 1024];
+
+            loop {
+                let n = match reader.read(&mut buf) {
+                    Ok(0) => break,
+                    Ok(n) => n,
+                    Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(e.into()),
+                };
+
+                fingerprinter.write(&buf[..n]);
+                file.write_all(&buf[..n])?;
+            }
+
+            file.sync_all()?;
+            fingerprinter.digest()
+        };
+
+        let path = self.path(&fprint);
+        std::fs::create_dir_all(path.parent().expect("blob paths have a parent"))?;
+
+        // `publish` doesn't replace an existing blob; if it linked
+        // `temp` into place, the temporary file is removed when `temp`
+        // is dropped.
+        loop {
+            if publish(&mut temp, &path)? {
+                return Ok(fprint);
+            }
+
+            match same_contents(&temp.path, &path) {
+                Ok(true) => return Ok(fprint),
+                Ok(false) => break,
+                // The blob was removed in the meantime; try again.
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        // Different bytes: the stored blob is either a genuine
+        // collision, or corrupt, and we replace it.
+        match self.fingerprint_file(&path) {
+            Ok(found) if found == fprint => return Err(CasError::Collision(fprint)),
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        std::fs::rename(&temp.path, &path)?;
+        temp.keep = true;
+        Ok(fprint)
+    }
+
+    /// Returns the [`Fingerprint`] of the contents of the file at
+    /// `path`.
+    fn fingerprint_file(&self, path: &Path) -> std::io::Result<Fingerprint> {
+        let mut fingerprinter = self.params().fingerprinter(0);
+
+        std::io::copy(&mut File::open(path)?, &mut fingerprinter)?;
+        Ok(fingerprinter.digest())
+    }
+
+    /// Returns the blob for `fprint`, or `None` if there is no such
+    /// blob.
+    ///
+    /// Fails with [`CasError::Corrupt`] if the stored bytes do not
+    /// match `fprint`.
+    pub fn get(&self, fprint: &Fingerprint) -> Result<Option<Vec<u8>>, CasError> {
+        let bytes = match std::fs::read(self.path(fprint)) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        if !self.params().verify(0, fprint, &bytes).full {
+            return Err(CasError::Corrupt(*fprint));
+        }
+
+        Ok(Some(bytes))
+    }
+
+    /// Removes the blob for `fprint`, and returns whether it was
+    /// present.
+    pub fn remove(&self, fprint: &Fingerprint) -> Result<bool, CasError> {
+        match std::fs::remove_file(self.path(fprint)) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+impl<P: Borrow<Params>> std::fmt::Debug for BlobStore<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BlobStore")
+            .field("params", &self.params().id())
+            .field("root", &self.root)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cas::{BlobStore, CasError};
+    use crate::test_util::scratch_dir;
+    use crate::Params;
+
+    #[test]
+    fn test_put_get() {
+        let dir = scratch_dir("cas-put-get");
+        let params = Params::derive(0, b"cas");
+        let store = BlobStore::open(&params, &dir).expect("must open");
+
+        let fprint = store.put(b"hello world").expect("must store");
+        assert_eq!(
+            fprint,
+            params.fingerprinter(0).write(b"hello world").digest()
+        );
+        assert!(store.contains(&fprint));
+        assert!(store
+            .path(&fprint)
+            .starts_with(dir.join(&fprint.to_string()[..2])));
+        assert_eq!(
+            store.get(&fprint).expect("must read"),
+            Some(b"hello world".to_vec())
+        );
+
+        // Idempotent, and no leftover temporary file.
+        assert_eq!(store.put(b"hello world").expect("must store"), fprint);
+        assert_eq!(
+            std::fs::read_dir(dir.join("tmp"))
+                .expect("must list")
+                .count(),
+            0
+        );
+
+        let large = vec![7u8; 200_000];
+        let large_fprint = store.put_reader(&large[..]).expect("must store");
+        assert_eq!(store.get(&large_fprint).expect("must read"), Some(large));
+
+        assert!(store.remove(&fprint).expect("must remove"));
+        assert!(!store.remove(&fprint).expect("must succeed"));
+        assert_eq!(store.get(&fprint).expect("must succeed"), None);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_concurrent_puts() {
+        let dir = scratch_dir("cas-concurrent");
+        let params = Params::derive(0, b"cas");
+        let bytes = vec![42u8; 100_000];
+
+        let workers: Vec<_> = (0..8)
+            .map(|_| {
+                let (params, dir, bytes) = (params.clone(), dir.clone(), bytes.clone());
+
+                std::thread::spawn(move || {
+                    let store = BlobStore::open(params, dir).expect("must open");
+
+                    store.put(&bytes).expect("must store")
+                })
+            })
+            .collect();
+        let fprints: Vec<_> = workers
+            .into_iter()
+            .map(|worker| worker.join().expect("must not panic"))
+            .collect();
+
+        let store = BlobStore::open(&params, &dir).expect("must open");
+        assert!(fprints.iter().all(|fprint| *fprint == fprints[0]));
+        assert_eq!(store.get(&fprints[0]).expect("must read"), Some(bytes));
+        assert_eq!(
+            std::fs::read_dir(dir.join("tmp"))
+                .expect("must list")
+                .count(),
+            0
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_corruption() {
+        let dir = scratch_dir("cas-corruption");
+        let params = Params::derive(0, b"cas");
+        let store = BlobStore::open(&params, &dir).expect("must open");
+        let fprint = store.put(b"precious data").expect("must store");
+
+        std::fs::write(store.path(&fprint), b"bit rot").expect("must write");
+        assert!(matches!(store.get(&fprint), Err(CasError::Corrupt(x)) if x == fprint));
+
+        // Storing the original bytes again repairs the blob.
+        assert_eq!(store.put(b"precious data").expect("must repair"), fprint);
+        assert_eq!(
+            store.get(&fprint).expect("must read"),
+            Some(b"precious data".to_vec())
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_collision() {
+        let dir = scratch_dir("cas-collision");
+        // All-zero parameters map every input longer than 8 bytes to
+        // the same fingerprint.
+        let params = Params(unsafe { std::mem::zeroed() });
+        let store = BlobStore::open(&params, &dir).expect("must open");
+
+        let fprint = store.put(b"the first blob").expect("must store");
+        assert_eq!(store.put(b"the first blob").expect("must store"), fprint);
+        assert!(matches!(
+            store.put(b"a colliding blob"),
+            Err(CasError::Collision(x)) if x == fprint
+        ));
+        assert_eq!(
+            CasError::Collision(fprint).to_string(),
+            format!("fingerprint collision for blob {}", fprint)
+        );
+
+        // The first blob is still there, and no temporary file leaks.
+        assert_eq!(
+            store.get(&fprint).expect("must read"),
+            Some(b"the first blob".to_vec())
+        );
+        assert_eq!(
+            std::fs::read_dir(dir.join("tmp"))
+                .expect("must list")
+                .count(),
+            0
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_params_mismatch() {
+        let dir = scratch_dir("cas-params");
+        let params = Params::derive(0, b"cas");
+        let other = Params::derive(1, b"cas");
+
+        drop(BlobStore::open(&params, &dir).expect("must create"));
+        assert!(BlobStore::open(&params, &dir).is_ok());
+        assert!(matches!(
+            BlobStore::open(&other, &dir),
+            Err(CasError::ParamsMismatch { expected, found })
+                if expected == other.id() && found == params.id()
+        ));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
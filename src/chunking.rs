@@ -0,0 +1,335 @@
+//! Content-defined chunking (CDC): split a byte stream into chunks
+//! whose boundaries depend on the content, so that inserting or
+//! deleting bytes only changes the chunks around the edit.
+//!
+//! The [`Chunker`] implements FastCDC's normalised chunking (Xia et
+//! al., "FastCDC: a Fast and Efficient Content-Defined Chunking
+//! Approach for Data Deduplication"): a gear rolling hash is updated
+//! for each byte after the minimum chunk size, with a stricter
+//! boundary condition before the average chunk size, and a looser one
+//! after, until the maximum chunk size forces a boundary.  Each chunk
+//! is then fingerprinted with UMASH.
+//!
+//! The gear table is derived from the [`Params`], so chunk boundaries
+//! (and not only fingerprints) depend on the [`Params`]: use
+//! [`Params::derive`] for boundaries that are stable across processes.
+use crate::{Fingerprint, Fingerprinter, Params};
+use std::io::Read;
+
+// The gear table is computed with a fixed seed, to keep it
+// independent of the fingerprints.
+const GEAR_SEED: u64 = 0x6765_6172; // "gear"
+
+/// Chunk size bounds for a [`Chunker`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ChunkerConfig {
+    /// No chunk is shorter than `min_size` bytes, except for the last.
+    pub min_size: usize,
+    /// The target average chunk size, in bytes.
+    pub avg_size: usize,
+    /// No chunk is longer than `max_size` bytes.
+    pub max_size: usize,
+}
+
+/// The default configuration targets 8 KiB chunks, between 2 KiB
+/// and 64 KiB.
+impl Default for ChunkerConfig {
+    fn default() -> Self {
+        ChunkerConfig {
+            min_size: 2 * 1024,
+            avg_size: 8 * 1024,
+            max_size: 64 * 1024,
+        }
+    }
+}
+
+/// A [`Chunk`] describes one chunk in a byte stream.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Chunk {
+    /// The offset of the chunk's first byte in the stream.
+    pub offset: u64,
+    /// The number of bytes in the chunk.
+    pub len: usize,
+    /// The [`Fingerprint`] of the chunk's bytes.
+    pub fingerprint: Fingerprint,
+}
+
+/// A [`Chunker`] splits the bytes from a [`Read`] into
+/// content-defined [`Chunk`]s, and yields them as an [`Iterator`].
+pub struct Chunker<'params, R: Read> {
+    reader: R,
+    fingerprinter: Fingerprinter<'params>,
+    gear: Box<[u64; 256]>,
+    config: ChunkerConfig,
+    // Stricter mask before `avg_size`, looser one after.
+    mask_small: u64,
+    mask_large: u64,
+    buf: Vec<u8>,
+    offset: u64,
+    eof: bool,
+}
+
+/// Returns a mask with the top `bits` bits set.
+fn top_bits(bits: u32) -> u64 {
+    if bits == 0 {
+        0
+    } else {
+        !0u64 << (64 - bits.min(64))
+    }
+}
+
+impl<'params, R: Read> Chunker<'params, R> {
+    /// Returns a [`Chunker`] that reads `reader`, finds boundaries
+    /// according to `config`, and fingerprints chunks with `params`
+    /// and `seed = 0`.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `0 < min_size <= avg_size <= max_size`.
+    pub fn new(params: &'params Params, reader: R, config: ChunkerConfig) -> Self {
+        assert!(
+            0 < config.min_size
+                && config.min_size <= config.avg_size
+                && config.avg_size <= config.max_size,
+            "chunk sizes must satisfy 0 < min <= avg <= max"
+        );
+
+        let mut gear = Box::new([0u64; 256]);
+        let mut hasher = params.hasher(GEAR_SEED);
+        for (i, slot) in gear.iter_mut().enumerate() {
+            *slot = hasher.reset().write(&[i as u8]).digest();
+        }
+
+        // A boundary test with `bits` zero bits succeeds with
+        // probability 2^-bits; normalise by 2 bits on each side of
+        // the average.
+        let bits = (config.avg_size as f64).log2().round() as u32;
+        Chunker {
+            reader,
+            fingerprinter: params.fingerprinter(0),
+            gear,
+            config,
+            mask_small: top_bits(bits + 2),
+            mask_large: top_bits(bits.saturating_sub(2)),
+            buf: Vec::with_capacity(2 * config.max_size),
+            offset: 0,
+            eof: false,
+        }
+    }
+
+    /// Returns the length of the first chunk in `data`, which is
+    /// either at least `max_size` bytes long, or the tail of the
+    /// stream.
+    fn cut(&self, data: &[u8]) -> usize {
+        let len = data.len();
+
+        if len <= self.config.min_size {
+            return len;
+        }
+
+        let normal = self.config.avg_size.min(len);
+        let max = self.config.max_size.min(len);
+        let mut hash = 0u64;
+        let mut i = self.config.min_size;
+
+        while i < normal {
+            hash = (hash << 1).wrapping_add(self.gear[data[i] as usize]);
+            if hash & self.mask_small == 0 {
+                return i + 1;
+            }
+
+            i += 1;
+        }
+
+        while i < max {
+            hash = (hash << 1).wrapping_add(self.gear[data[i] as usize]);
+            if hash & self.mask_large == 0 {
+                return i + 1;
+            }
+
+            i += 1;
+        }
+
+        max
+    }
+
+    /// Reads until the buffer holds at least `max_size` bytes, or the
+    /// reader is exhausted.
+    fn fill(&mut self) -> std::io::Result<()> {
+        let mut block = [0u8; 8192];
+
+        while !self.eof && self.buf.len() < self.config.max_size {
+            match self.reader.read(&mut block) {
+                Ok(0) => self.eof = true,
+                Ok(n) => self.buf.extend_from_slice(&block[..n]),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<R: Read> Iterator for Chunker<'_, R> {
+    type Item = std::io::Result<Chunk>;
+
+    fn next(&mut self) -> Option<std::io::Result<Chunk>> {
+        if let Err(e) = self.fill() {
+            return Some(Err(e));
+        }
+
+        if self.buf.is_empty() {
+            return None;
+        }
+
+        let len = self.cut(&self.buf);
+        let chunk = Chunk {
+            offset: self.offset,
+            len,
+            fingerprint: self.fingerprinter.reset().write(&self.buf[..len]).digest(),
+        };
+
+        self.buf.drain(..len);
+        self.offset += len as u64;
+        Some(Ok(chunk))
+    }
+}
+
+impl<R: Read> std::fmt::Debug for Chunker<'_, R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Chunker")
+            .field("params", &self.fingerprinter.params.id())
+            .field("config", &self.config)
+            .field("offset", &self.offset)
+            .field("buffered", &self.buf.len())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chunking::{Chunk, Chunker, ChunkerConfig};
+    use crate::Params;
+    use std::collections::HashSet;
+
+    // Deterministic pseudorandom bytes (xorshift64).
+    fn random_bytes(len: usize, mut state: u64) -> Vec<u8> {
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    }
+
+    fn chunks(params: &Params, data: &[u8]) -> Vec<Chunk> {
+        Chunker::new(params, data, ChunkerConfig::default())
+            .collect::<std::io::Result<Vec<_>>>()
+            .expect("reading from a slice never fails")
+    }
+
+    // A reader that returns at most 7 bytes at a time.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl std::io::Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = buf.len().min(self.0.len()).min(7);
+
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_chunk_bounds() {
+        let params = Params::derive(0, b"chunking");
+        let data = random_bytes(1 << 20, 1);
+        let chunks = chunks(&params, &data);
+        let config = ChunkerConfig::default();
+        let mut offset = 0;
+
+        assert!(chunks.len() > 50 && chunks.len() < 250, "{}", chunks.len());
+        for (i, chunk) in chunks.iter().enumerate() {
+            let bytes = &data[offset as usize..offset as usize + chunk.len];
+
+            assert_eq!(chunk.offset, offset);
+            assert!(chunk.len <= config.max_size);
+            assert!(chunk.len >= config.min_size || i + 1 == chunks.len());
+            assert_eq!(
+                chunk.fingerprint,
+                params.fingerprinter(0).write(bytes).digest()
+            );
+            offset += chunk.len as u64;
+        }
+
+        assert_eq!(offset, data.len() as u64);
+
+        let trickled = Chunker::new(&params, Trickle(&data), config)
+            .collect::<std::io::Result<Vec<_>>>()
+            .expect("must succeed");
+        assert_eq!(trickled, chunks);
+    }
+
+    #[test]
+    fn test_boundary_stability() {
+        let params = Params::derive(0, b"chunking");
+        let data = random_bytes(1 << 20, 2);
+        let mut edited = data.clone();
+
+        edited.splice(300_000..300_000, random_bytes(100, 3));
+        edited.splice(700_000..700_010, std::iter::empty());
+
+        let original = chunks(&params, &data);
+        let edited = chunks(&params, &edited);
+        let known: HashSet<_> = original.iter().map(|chunk| chunk.fingerprint).collect();
+        let changed = edited
+            .iter()
+            .filter(|chunk| !known.contains(&chunk.fingerprint))
+            .count();
+
+        // Each edit should only affect one or two chunks.
+        assert!(
+            changed <= 4,
+            "{} of {} chunks changed",
+            changed,
+            edited.len()
+        );
+        assert_eq!(original[0], edited[0]);
+        assert_eq!(
+            original.last().map(|chunk| chunk.fingerprint),
+            edited.last().map(|chunk| chunk.fingerprint)
+        );
+    }
+
+    #[test]
+    fn test_small_inputs() {
+        let params = Params::derive(0, b"chunking");
+
+        assert!(chunks(&params, b"").is_empty());
+        assert_eq!(
+            chunks(&params, b"short"),
+            vec![Chunk {
+                offset: 0,
+                len: 5,
+                fingerprint: params.fingerprinter(0).write(b"short").digest(),
+            }]
+        );
+
+        // Boundaries depend on the params.
+        let data = random_bytes(1 << 18, 4);
+        assert_ne!(
+            chunks(&params, &data)
+                .iter()
+                .map(|chunk| chunk.len)
+                .collect::<Vec<_>>(),
+            chunks(&Params::derive(1, b"chunking"), &data)
+                .iter()
+                .map(|chunk| chunk.len)
+                .collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,559 @@
+//! A Count-Min sketch to estimate the frequency of items in a stream,
+//! and a heavy hitters tracker built on top of the sketch.
+//!
+//! A [`CountMinSketch`] has `depth` rows of `width` counters.  Row `i`
+//! hashes items with the primary [`UmashComponent::Hash`](crate::UmashComponent::Hash)
+//! function of its own [`Params`], and maps the hash value to a
+//! counter in that row with [`hash_to_range`].  The estimated count
+//! for an item is the minimum of its counters: estimates never
+//! undercount, and, with `width = ceil(e / epsilon)` and
+//! `depth = ceil(ln(1 / delta))`, overcount by more than
+//! `epsilon * total` with probability at most `delta`.
+//!
+//! That bound assumes the rows hash independently.  Changing the
+//! `seed` for a given [`Params`] comes with no proven bound, so, as
+//! for [`MinHasher::derive`](crate::minhash::MinHasher::derive), each
+//! row instead uses [`Params::derive`] with `bits = i`, and a key
+//! derived from the sketch's [`Params`].  The rows are then as
+//! independent as the [`Params::derive`] outputs.
+//!
+//! Hash raw bytes (e.g., `&[u8]` or `&str`) when sketches are merged
+//! across programs (see the [crate docs](crate#data-structures)).
+use crate::codec::{check_params, Decoder, Encoder};
+use crate::range::hash_to_range;
+use crate::{DecodeError, MergeError, Params};
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+const MAGIC: &[u8; 4] = b"UMCM";
+const VERSION: u8 = 1;
+
+// Hashed with the sketch's `Params` to derive the key for each row's
+// `Params`.
+const ROW_KEY_LABEL: &[u8] = b"umash count-min sketch rows";
+
+/// A [`CountMinSketch`] estimates the number of times each item was
+/// added to it, in space independent of the number of distinct items.
+///
+/// See the [crate docs](crate#data-structures) for the choice of
+/// `P`.
+#[derive(Clone)]
+pub struct CountMinSketch<P: Borrow<Params>> {
+    params: P,
+    // One `Params` per row, derived from `params` with `row_params`.
+    rows: Vec<Params>,
+    width: u32,
+    depth: u32,
+    // Sum of all the counts added to the sketch.
+    total: u64,
+    // `depth` rows of `width` counters, row-major.
+    counters: Vec<u64>,
+}
+
+/// Returns the `(width, depth)` for a [`CountMinSketch`] whose estimates
+/// exceed the true count by at most `epsilon` times the total count,
+/// with probability at least `1 - delta`.
+///
+/// # Panics
+///
+/// Panics if `epsilon` or `delta` is not strictly between 0 and 1.
+pub fn optimal_dimensions(epsilon: f64, delta: f64) -> (u32, u32) {
+    assert!(
+        epsilon > 0.0 && epsilon < 1.0,
+        "epsilon must be strictly between 0 and 1"
+    );
+    assert!(
+        delta > 0.0 && delta < 1.0,
+        "delta must be strictly between 0 and 1"
+    );
+
+    let width = (std::f64::consts::E / epsilon).ceil();
+    let depth = (1.0 / delta).ln().ceil().max(1.0);
+
+    (width as u32, depth as u32)
+}
+
+/// Returns the [`Params`] for each of the `depth` rows in a sketch
+/// with `params`.
+fn row_params(params: &Params, depth: u32) -> Vec<Params> {
+    let mut key = [0u8; 32];
+    params.fill_key(ROW_KEY_LABEL, &mut key);
+
+    let ret = (0..depth as u64)
+        .map(|row| Params::derive(row, &key))
+        .collect();
+
+    #[cfg(feature = "zeroize")]
+    zeroize::Zeroize::zeroize(&mut key);
+
+    ret
+}
+
+impl<P: Borrow<Params>> CountMinSketch<P> {
+    /// Returns an empty [`CountMinSketch`] with `depth` rows of `width`
+    /// counters.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` or `depth` is zero.
+    pub fn new(params: P, width: u32, depth: u32) -> Self {
+        assert!(width > 0, "Count-Min sketches need at least one column");
+        assert!(depth > 0, "Count-Min sketches need at least one row");
+
+        CountMinSketch {
+            rows: row_params(params.borrow(), depth),
+            params,
+            width,
+            depth,
+            total: 0,
+            counters: vec![0; width as usize * depth as usize],
+        }
+    }
+
+    /// Returns an empty [`CountMinSketch`] sized with
+    /// [`optimal_dimensions`] for `epsilon` and `delta`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `epsilon` or `delta` is not strictly between 0 and 1.
+    pub fn with_error(params: P, epsilon: f64, delta: f64) -> Self {
+        let (width, depth) = optimal_dimensions(epsilon, delta);
+
+        Self::new(params, width, depth)
+    }
+
+    /// Returns the [`Params`] for this sketch.
+    #[inline(always)]
+    pub fn params(&self) -> &Params {
+        self.params.borrow()
+    }
+
+    /// Returns the number of counters in each row.
+    #[inline(always)]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the number of rows.
+    #[inline(always)]
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    /// Returns the sum of all the counts added to the sketch.
+    #[inline(always)]
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Returns the index in `counters` for `item` in `row`.
+    #[inline(always)]
+    fn index<T: Hash + ?Sized>(&self, row: usize, item: &T) -> usize {
+        let width = self.width as usize;
+
+        row * width + hash_to_range(self.rows[row].hash(item), width as u64) as usize
+    }
+
+    /// Adds `count` occurrences of `item` to the sketch.
+    pub fn add<T: Hash + ?Sized>(&mut self, item: &T, count: u64) {
+        for row in 0..self.rows.len() {
+            let index = self.index(row, item);
+
+            self.counters[index] = self.counters[index].saturating_add(count);
+        }
+
+        self.total = self.total.saturating_add(count);
+    }
+
+    /// Adds `count` occurrences of `item` to the sketch, with the
+    /// conservative update rule: counters are only increased as much
+    /// as necessary to bring the estimate for `item` up by `count`.
+    /// This reduces overcounting, but the result of later merges is
+    /// then only an upper bound on the result of conservative updates
+    /// on a single sketch.
+    ///
+    /// Returns the new estimate for `item`.
+    pub fn add_conservative<T: Hash + ?Sized>(&mut self, item: &T, count: u64) -> u64 {
+        // Hash `item` twice, rather than allocating a buffer of
+        // `depth` indices.
+        let target = self.estimate(item).saturating_add(count);
+
+        for row in 0..self.rows.len() {
+            let index = self.index(row, item);
+
+            self.counters[index] = self.counters[index].max(target);
+        }
+
+        self.total = self.total.saturating_add(count);
+        target
+    }
+
+    /// Returns an upper bound on the number of occurrences of `item`.
+    pub fn estimate<T: Hash + ?Sized>(&self, item: &T) -> u64 {
+        (0..self.rows.len())
+            .map(|row| self.counters[self.index(row, item)])
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Adds all the counts in `other` to this sketch.  The result is
+    /// the same as if all the items in `other` had been added to
+    /// `self` with [`CountMinSketch::add`].
+    ///
+    /// Fails without modifying `self` if the two sketches have
+    /// different [`Params`] or dimensions.
+    pub fn merge<Q: Borrow<Params>>(
+        &mut self,
+        other: &CountMinSketch<Q>,
+    ) -> Result<(), MergeError> {
+        check_params(self.params().id(), other.params().id())?;
+        if self.width != other.width || self.depth != other.depth {
+            return Err(MergeError::ShapeMismatch);
+        }
+
+        for (dst, src) in self.counters.iter_mut().zip(other.counters.iter()) {
+            *dst = dst.saturating_add(*src);
+        }
+
+        self.total = self.total.saturating_add(other.total);
+        Ok(())
+    }
+
+    /// Resets all counters to zero.
+    pub fn clear(&mut self) {
+        self.counters.iter_mut().for_each(|counter| *counter = 0);
+        self.total = 0;
+    }
+
+    /// Serialises the sketch to bytes, including the
+    /// [`ParamsId`](crate::ParamsId) of the sketch's [`Params`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut encoder = Encoder::new(MAGIC, VERSION, self.params().id());
+
+        encoder.u32(self.width).u32(self.depth).u64(self.total);
+        for counter in self.counters.iter() {
+            encoder.u64(*counter);
+        }
+
+        encoder.finish()
+    }
+
+    /// Deserialises a sketch serialised by [`CountMinSketch::to_bytes`].
+    ///
+    /// Fails if `params` is not the [`Params`] that built the sketch.
+    pub fn from_bytes(params: P, bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut decoder = Decoder::new(bytes, MAGIC, VERSION, params.borrow().id())?;
+        let width = decoder.u32()?;
+        let depth = decoder.u32()?;
+        let total = decoder.u64()?;
+
+        if width == 0 || depth == 0 {
+            return Err(DecodeError::Invalid("empty Count-Min sketch"));
+        }
+
+        let num_counters = width as u64 * depth as u64;
+        let num_bytes = num_counters
+            .checked_mul(8)
+            .ok_or(DecodeError::Invalid("Count-Min sketch too large"))?;
+        if decoder.remaining() as u64 != num_bytes {
+            return Err(DecodeError::Invalid("Count-Min sketch size mismatch"));
+        }
+
+        let counters = (0..num_counters)
+            .map(|_| decoder.u64())
+            .collect::<Result<Vec<_>, _>>()?;
+        decoder.finish()?;
+
+        Ok(CountMinSketch {
+            rows: row_params(params.borrow(), depth),
+            params,
+            width,
+            depth,
+            total,
+            counters,
+        })
+    }
+}
+
+/// See the [crate docs](crate#data-structures) for the
+/// [`Debug`](std::fmt::Debug) representation of data structures.
+impl<P: Borrow<Params>> std::fmt::Debug for CountMinSketch<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CountMinSketch")
+            .field("params", &self.params().id())
+            .field("width", &self.width)
+            .field("depth", &self.depth)
+            .field("total", &self.total)
+            .finish()
+    }
+}
+
+/// A [`HeavyHitters`] tracker finds the (approximately) `k` most
+/// frequent items in a stream: it counts all items in a
+/// [`CountMinSketch`] with conservative updates, and remembers the `k`
+/// items with the highest estimates so far.
+///
+/// Each update takes `O(depth + k)` time, so `k` should stay small
+/// (up to a few hundred).
+#[derive(Clone)]
+pub struct HeavyHitters<P: Borrow<Params>, K> {
+    sketch: CountMinSketch<P>,
+    k: usize,
+    top: HashMap<K, u64>,
+}
+
+impl<P: Borrow<Params>, K: Hash + Eq + Clone> HeavyHitters<P, K> {
+    /// Returns an empty [`HeavyHitters`] tracker for the `k` most
+    /// frequent items, on top of `sketch`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is zero.
+    pub fn new(sketch: CountMinSketch<P>, k: usize) -> Self {
+        assert!(k > 0, "must track at least one item");
+
+        HeavyHitters {
+            sketch,
+            k,
+            top: HashMap::with_capacity(k + 1),
+        }
+    }
+
+    /// Returns the underlying [`CountMinSketch`].
+    #[inline(always)]
+    pub fn sketch(&self) -> &CountMinSketch<P> {
+        &self.sketch
+    }
+
+    /// Returns the maximum number of items tracked.
+    #[inline(always)]
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Adds `count` occurrences of `item`, and returns the new
+    /// estimate for `item`.
+    pub fn add(&mut self, item: K, count: u64) -> u64 {
+        let estimate = self.sketch.add_conservative(&item, count);
+
+        self.offer(item, estimate);
+        estimate
+    }
+
+    /// Tracks `item` with `estimate` if it's among the top `k`.
+    fn offer(&mut self, item: K, estimate: u64) {
+        if let Some(slot) = self.top.get_mut(&item) {
+            *slot = estimate;
+            return;
+        }
+
+        if self.top.len() < self.k {
+            self.top.insert(item, estimate);
+            return;
+        }
+
+        let (min_item, min_estimate) = self
+            .top
+            .iter()
+            .min_by_key(|(_, estimate)| **estimate)
+            .map(|(item, estimate)| (item.clone(), *estimate))
+            .expect("k > 0");
+        if estimate > min_estimate {
+            self.top.remove(&min_item);
+            self.top.insert(item, estimate);
+        }
+    }
+
+    /// Returns the tracked items and their estimated counts, in
+    /// decreasing order of estimates.
+    pub fn top(&self) -> Vec<(K, u64)> {
+        let mut ret: Vec<_> = self
+            .top
+            .iter()
+            .map(|(item, estimate)| (item.clone(), *estimate))
+            .collect();
+
+        ret.sort_by_key(|(_, estimate)| std::cmp::Reverse(*estimate));
+        ret
+    }
+
+    /// Merges the counts in `other` into this tracker, and recomputes
+    /// the top `k` among the items tracked by either.
+    ///
+    /// Fails without modifying `self` if the two sketches can't be
+    /// merged.
+    pub fn merge<Q: Borrow<Params>>(
+        &mut self,
+        other: &HeavyHitters<Q, K>,
+    ) -> Result<(), MergeError> {
+        self.sketch.merge(&other.sketch)?;
+
+        let candidates: Vec<K> = self.top.keys().chain(other.top.keys()).cloned().collect();
+        self.top.clear();
+        for item in candidates {
+            let estimate = self.sketch.estimate(&item);
+            self.offer(item, estimate);
+        }
+
+        Ok(())
+    }
+}
+
+impl<P: Borrow<Params>, K> std::fmt::Debug for HeavyHitters<P, K> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HeavyHitters")
+            .field("sketch", &self.sketch)
+            .field("k", &self.k)
+            .field("tracked", &self.top.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cms::{optimal_dimensions, row_params, CountMinSketch, HeavyHitters};
+    use crate::{DecodeError, MergeError, Params};
+
+    #[test]
+    fn test_estimates() {
+        let params = Params::derive(0, b"cms");
+        let mut plain = CountMinSketch::with_error(&params, 0.01, 0.01);
+        let mut conservative = CountMinSketch::with_error(&params, 0.01, 0.01);
+
+        assert_eq!(optimal_dimensions(0.01, 0.01), (272, 5));
+        for i in 0..2000u32 {
+            // Item `i` occurs `1 + i % 10` times.
+            plain.add(&i, 1 + (i % 10) as u64);
+            conservative.add_conservative(&i, 1 + (i % 10) as u64);
+        }
+
+        assert_eq!(plain.total(), conservative.total());
+        let bound = (0.01 * plain.total() as f64) as u64;
+        let mut plain_error = 0;
+        let mut conservative_error = 0;
+        for i in 0..2000u32 {
+            let expected = 1 + (i % 10) as u64;
+            let estimate = plain.estimate(&i);
+            let tighter = conservative.estimate(&i);
+
+            assert!(estimate >= expected);
+            assert!(tighter >= expected && tighter <= estimate);
+            assert!(estimate - expected <= bound, "{} {}", i, estimate);
+            plain_error += estimate - expected;
+            conservative_error += tighter - expected;
+        }
+
+        assert!(conservative_error < plain_error);
+        assert!(plain.estimate("absent") <= bound);
+    }
+
+    #[test]
+    fn test_row_params() {
+        let params = Params::derive(0, b"cms");
+        let rows = row_params(&params, 3);
+        let mut ids: Vec<_> = rows.iter().map(|row| row.id()).collect();
+
+        ids.push(params.id());
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), 4);
+
+        // Deterministic, and a prefix for shallower sketches.
+        let shallow = row_params(&params, 2);
+        assert_eq!(shallow[1].id(), rows[1].id());
+        assert_ne!(
+            row_params(&Params::derive(1, b"cms"), 1)[0].id(),
+            rows[0].id()
+        );
+    }
+
+    #[test]
+    fn test_merge_and_bytes() {
+        let params = Params::derive(0, b"cms");
+        let mut left = CountMinSketch::new(&params, 64, 4);
+        let mut right = CountMinSketch::new(&params, 64, 4);
+        let mut both = CountMinSketch::new(&params, 64, 4);
+
+        for i in 0..100u32 {
+            left.add(&i, 2);
+            right.add(&(i + 50), 3);
+            both.add(&i, 2);
+            both.add(&(i + 50), 3);
+        }
+
+        left.merge(&right).expect("compatible");
+        assert_eq!(left.to_bytes(), both.to_bytes());
+
+        let other = Params::derive(1, b"cms");
+        assert_eq!(
+            left.merge(&CountMinSketch::new(&other, 64, 4)),
+            Err(MergeError::ParamsMismatch {
+                left: params.id(),
+                right: other.id()
+            })
+        );
+        assert_eq!(
+            left.merge(&CountMinSketch::new(&params, 64, 3)),
+            Err(MergeError::ShapeMismatch)
+        );
+
+        let bytes = left.to_bytes();
+        let copy = CountMinSketch::from_bytes(&params, &bytes).expect("valid encoding");
+        assert_eq!(copy.estimate(&75u32), left.estimate(&75u32));
+        assert_eq!(copy.total(), 500);
+        assert_eq!(
+            CountMinSketch::from_bytes(&params, &bytes[..bytes.len() - 1]).err(),
+            Some(DecodeError::Invalid("Count-Min sketch size mismatch"))
+        );
+
+        // width = depth = u32::MAX overflows the byte count.
+        let mut huge = bytes[..4 + 1 + 8].to_vec();
+        huge.extend_from_slice(&u32::MAX.to_le_bytes());
+        huge.extend_from_slice(&u32::MAX.to_le_bytes());
+        huge.extend_from_slice(&0u64.to_le_bytes());
+        assert_eq!(
+            CountMinSketch::from_bytes(&params, &huge).err(),
+            Some(DecodeError::Invalid("Count-Min sketch too large"))
+        );
+        assert_eq!(
+            CountMinSketch::from_bytes(&other, &bytes).err(),
+            Some(DecodeError::ParamsMismatch {
+                expected: other.id(),
+                found: params.id()
+            })
+        );
+
+        left.clear();
+        assert_eq!(left.total(), 0);
+        assert_eq!(left.estimate(&75u32), 0);
+    }
+
+    #[test]
+    fn test_heavy_hitters() {
+        let params = Params::derive(0, b"cms");
+        let mut left = HeavyHitters::new(CountMinSketch::new(&params, 256, 4), 3);
+        let mut right = HeavyHitters::new(CountMinSketch::new(&params, 256, 4), 3);
+
+        for i in 0..1000u32 {
+            left.add(i % 100, 1);
+            // Items 1000, 1001, 1002 are heavy hitters.
+            left.add(1000 + i % 3, 1);
+            right.add(2000 + i % 50, 1);
+        }
+
+        let top = left.top();
+        assert_eq!(top.len(), 3);
+        let mut items: Vec<_> = top.iter().map(|(item, _)| *item).collect();
+        items.sort();
+        assert_eq!(items, vec![1000, 1001, 1002]);
+        assert!(top.iter().all(|(_, count)| *count >= 333));
+
+        // After merging, the heavy hitters are still the same.
+        left.merge(&right).expect("compatible");
+        let mut items: Vec<_> = left.top().iter().map(|(item, _)| *item).collect();
+        items.sort();
+        assert_eq!(items, vec![1000, 1001, 1002]);
+        assert_eq!(left.sketch().total(), 3000);
+    }
+}
@@ -0,0 +1,216 @@
+//! Shared helpers to serialise the sketches and filters built on
+//! UMASH.
+//!
+//! Every encoding starts with the same header: 4 magic bytes that
+//! identify the data structure, a 1-byte format version, and the
+//! [`ParamsId`] of the [`Params`](crate::Params) that computed the
+//! hash values in the structure.  All integers are little-endian.
+use crate::ParamsId;
+
+/// A [`DecodeError`] describes why serialised bytes could not be
+/// decoded back into a data structure.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The input ended before the end of the encoded structure.
+    Truncated,
+
+    /// The input does not start with the magic bytes for the
+    /// expected data structure.
+    BadMagic,
+
+    /// The input was encoded with an unknown format version.
+    UnsupportedVersion(u8),
+
+    /// The input was built with a different set of
+    /// [`Params`](crate::Params) than the one passed to the decoder.
+    ParamsMismatch { expected: ParamsId, found: ParamsId },
+
+    /// The input is well formed, but describes an invalid data
+    /// structure.
+    Invalid(&'static str),
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::Truncated => write!(f, "truncated input"),
+            DecodeError::BadMagic => write!(f, "unexpected magic bytes"),
+            DecodeError::UnsupportedVersion(version) => {
+                write!(f, "unsupported format version {}", version)
+            }
+            DecodeError::ParamsMismatch { expected, found } => write!(
+                f,
+                "built with params {}, expected params {}",
+                found, expected
+            ),
+            DecodeError::Invalid(reason) => write!(f, "invalid encoding: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// A [`MergeError`] describes why two data structures could not be
+/// combined.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MergeError {
+    /// The two structures were built with different
+    /// [`Params`](crate::Params).
+    ParamsMismatch { left: ParamsId, right: ParamsId },
+
+    /// The two structures have different sizes or shapes.
+    ShapeMismatch,
+}
+
+impl std::fmt::Display for MergeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MergeError::ParamsMismatch { left, right } => {
+                write!(f, "mismatched params {} and {}", left, right)
+            }
+            MergeError::ShapeMismatch => write!(f, "mismatched shapes"),
+        }
+    }
+}
+
+impl std::error::Error for MergeError {}
+
+/// Returns `Ok(())` if `left` and `right` are the same, and a
+/// [`MergeError::ParamsMismatch`] otherwise.
+pub(crate) fn check_params(left: ParamsId, right: ParamsId) -> Result<(), MergeError> {
+    if left == right {
+        Ok(())
+    } else {
+        Err(MergeError::ParamsMismatch { left, right })
+    }
+}
+
+/// Serialises a data structure into a byte vector.
+pub(crate) struct Encoder {
+    buf: Vec<u8>,
+}
+
+impl Encoder {
+    /// Returns a fresh [`Encoder`], with the common header already
+    /// written.
+    pub(crate) fn new(magic: &[u8; 4], version: u8, params_id: ParamsId) -> Self {
+        let mut ret = Encoder { buf: Vec::new() };
+
+        ret.bytes(magic);
+        ret.u8(version);
+        ret.u64(params_id.0);
+        ret
+    }
+
+    pub(crate) fn u8(&mut self, value: u8) -> &mut Self {
+        self.buf.push(value);
+        self
+    }
+
+    pub(crate) fn u16(&mut self, value: u16) -> &mut Self {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    pub(crate) fn u32(&mut self, value: u32) -> &mut Self {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    pub(crate) fn u64(&mut self, value: u64) -> &mut Self {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    pub(crate) fn bytes(&mut self, bytes: &[u8]) -> &mut Self {
+        self.buf.extend_from_slice(bytes);
+        self
+    }
+
+    pub(crate) fn finish(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+/// Deserialises a data structure from a byte slice.
+pub(crate) struct Decoder<'a> {
+    rest: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    /// Returns a fresh [`Decoder`] after checking the common header.
+    pub(crate) fn new(
+        bytes: &'a [u8],
+        magic: &[u8; 4],
+        version: u8,
+        params_id: ParamsId,
+    ) -> Result<Self, DecodeError> {
+        let mut ret = Decoder { rest: bytes };
+
+        if ret.bytes(magic.len())? != magic {
+            return Err(DecodeError::BadMagic);
+        }
+
+        let found_version = ret.u8()?;
+        if found_version != version {
+            return Err(DecodeError::UnsupportedVersion(found_version));
+        }
+
+        let found = ParamsId(ret.u64()?);
+        if found != params_id {
+            return Err(DecodeError::ParamsMismatch {
+                expected: params_id,
+                found,
+            });
+        }
+
+        Ok(ret)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub(crate) fn u16(&mut self) -> Result<u16, DecodeError> {
+        let mut buf = [0u8; 2];
+
+        buf.copy_from_slice(self.bytes(2)?);
+        Ok(u16::from_le_bytes(buf))
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, DecodeError> {
+        let mut buf = [0u8; 4];
+
+        buf.copy_from_slice(self.bytes(4)?);
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, DecodeError> {
+        let mut buf = [0u8; 8];
+
+        buf.copy_from_slice(self.bytes(8)?);
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    /// Returns the next `len` bytes.
+    pub(crate) fn bytes(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        if self.rest.len() < len {
+            return Err(DecodeError::Truncated);
+        }
+
+        let (ret, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        Ok(ret)
+    }
+
+    /// Returns the number of bytes left to decode.
+    pub(crate) fn remaining(&self) -> usize {
+        self.rest.len()
+    }
+
+    /// Checks that the whole input was consumed.
+    pub(crate) fn finish(self) -> Result<(), DecodeError> {
+        if self.rest.is_empty() {
+            Ok(())
+        } else {
+            Err(DecodeError::Invalid("trailing bytes"))
+        }
+    }
+}
@@ -0,0 +1,435 @@
+//! A dynamic cuckoo filter (Fan et al., "Cuckoo Filter: Practically
+//! Better Than Bloom") for sets of items keyed by UMASH
+//! [`Fingerprint`]s.
+//!
+//! Each item is [`Params::fingerprint`]ed: the primary hash value
+//! selects the item's first bucket, and the secondary value provides
+//! a 16-bit tag.  The tag is stored in one of the item's two
+//! candidate buckets, each with 4 slots; the second bucket is derived
+//! from the first and the tag, so tags can move between buckets
+//! without the original item.  Unlike Bloom and xor filters, cuckoo
+//! filters support removals.
+//!
+//! Filters serialise with the [`ParamsId`](crate::ParamsId) of their
+//! [`Params`], so a filter built offline can be queried by another
+//! process that derives the same [`Params`] (e.g., with
+//! [`Params::derive`]).  Compute fingerprints of raw bytes and call
+//! the `_fingerprint` methods when filters are shared across programs
+//! (see the [crate docs](crate#data-structures)).
+use crate::codec::{Decoder, Encoder};
+use crate::{DecodeError, Fingerprint, Params};
+use std::borrow::Borrow;
+
+const MAGIC: &[u8; 4] = b"UMCF";
+const VERSION: u8 = 1;
+
+const BUCKET_SIZE: usize = 4;
+const MAX_KICKS: u32 = 500;
+// Empty slots hold this tag.
+const EMPTY: u16 = 0;
+
+type Bucket = [u16; BUCKET_SIZE];
+
+/// Returns the non-zero 16-bit tag for `fprint`.
+#[inline(always)]
+fn tag(fprint: Fingerprint) -> u16 {
+    match fprint.secondary() as u16 {
+        EMPTY => 1,
+        tag => tag,
+    }
+}
+
+/// A [`CuckooFilter`] is an approximate set that supports insertions
+/// and removals: it never reports false negatives for items that were
+/// inserted (and not removed), and reports false positives with
+/// probability about `2^-13`.
+///
+/// See the [crate docs](crate#data-structures) for the choice of
+/// `P`.
+#[derive(Clone)]
+pub struct CuckooFilter<P: Borrow<Params>> {
+    params: P,
+    // A power of two number of buckets.
+    buckets: Vec<Bucket>,
+    len: u64,
+    // A tag that was evicted when the filter filled up, and its bucket.
+    victim: Option<(u32, u16)>,
+    // Xorshift state to pick tags to evict.
+    rng: u64,
+}
+
+impl<P: Borrow<Params>> CuckooFilter<P> {
+    /// Returns an empty [`CuckooFilter`] with room for at least
+    /// `capacity` items.
+    pub fn new(params: P, capacity: usize) -> Self {
+        // Cuckoo filters with 4-slot buckets fill up at ~95% load.
+        let num_buckets = ((capacity as f64 / (0.95 * BUCKET_SIZE as f64)).ceil() as usize)
+            .max(1)
+            .next_power_of_two();
+
+        CuckooFilter {
+            params,
+            buckets: vec![[EMPTY; BUCKET_SIZE]; num_buckets],
+            len: 0,
+            victim: None,
+            rng: 0x9e3779b97f4a7c15,
+        }
+    }
+
+    /// Returns the [`Params`] for this filter.
+    #[inline(always)]
+    pub fn params(&self) -> &Params {
+        self.params.borrow()
+    }
+
+    /// Returns the number of items in the filter.
+    #[inline(always)]
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns whether the filter is empty.
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of slots in the filter.
+    #[inline(always)]
+    pub fn capacity(&self) -> usize {
+        self.buckets.len() * BUCKET_SIZE
+    }
+
+    #[inline(always)]
+    fn index(&self, fprint: Fingerprint) -> u32 {
+        (fprint.hash() & (self.buckets.len() as u64 - 1)) as u32
+    }
+
+    #[inline(always)]
+    fn alt_index(&self, index: u32, tag: u16) -> u32 {
+        let mask = self.buckets.len() as u64 - 1;
+
+        ((index as u64 ^ (tag as u64).wrapping_mul(0x5bd1e995)) & mask) as u32
+    }
+
+    fn next_random(&mut self) -> u64 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        self.rng
+    }
+
+    /// Stores `tag` in an empty slot of bucket `index`, if any.
+    fn try_put(&mut self, index: u32, tag: u16) -> bool {
+        match self.buckets[index as usize]
+            .iter_mut()
+            .find(|slot| **slot == EMPTY)
+        {
+            Some(slot) => {
+                *slot = tag;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stores `tag` in bucket `index` or its alternate, evicting other
+    /// tags as needed.  Stashes the last evicted tag in `victim` if
+    /// the filter is full.
+    fn place(&mut self, index: u32, tag: u16) {
+        let alt = self.alt_index(index, tag);
+
+        if self.try_put(index, tag) || self.try_put(alt, tag) {
+            return;
+        }
+
+        let mut index = if self.next_random() & 1 == 0 {
+            index
+        } else {
+            alt
+        };
+        let mut tag = tag;
+        for _ in 0..MAX_KICKS {
+            let slot = (self.next_random() % BUCKET_SIZE as u64) as usize;
+
+            std::mem::swap(&mut self.buckets[index as usize][slot], &mut tag);
+            index = self.alt_index(index, tag);
+            if self.try_put(index, tag) {
+                return;
+            }
+        }
+
+        self.victim = Some((index, tag));
+    }
+
+    /// Adds `item` to the filter.
+    ///
+    /// Returns false if the filter is full.
+    pub fn insert<T: std::hash::Hash + ?Sized>(&mut self, item: &T) -> bool {
+        let fprint = self.params().fingerprint(item);
+
+        self.insert_fingerprint(fprint)
+    }
+
+    /// Adds the item with [`Fingerprint`] `fprint` to the filter.  The
+    /// fingerprint must have been computed with this filter's
+    /// [`Params`].
+    ///
+    /// Returns false if the filter is full.  Inserting the same item
+    /// twice stores it twice.
+    pub fn insert_fingerprint(&mut self, fprint: Fingerprint) -> bool {
+        if self.victim.is_some() {
+            return false;
+        }
+
+        self.place(self.index(fprint), tag(fprint));
+        self.len += 1;
+        true
+    }
+
+    /// Determines whether `item` is probably in the filter.
+    pub fn contains<T: std::hash::Hash + ?Sized>(&self, item: &T) -> bool {
+        self.contains_fingerprint(self.params().fingerprint(item))
+    }
+
+    /// Determines whether the item with [`Fingerprint`] `fprint` is
+    /// probably in the filter.  The fingerprint must have been
+    /// computed with this filter's [`Params`].
+    pub fn contains_fingerprint(&self, fprint: Fingerprint) -> bool {
+        let tag = tag(fprint);
+        let index = self.index(fprint);
+        let alt = self.alt_index(index, tag);
+
+        self.buckets[index as usize].contains(&tag)
+            || self.buckets[alt as usize].contains(&tag)
+            || matches!(self.victim, Some((i, t)) if t == tag && (i == index || i == alt))
+    }
+
+    /// Removes `item` from the filter, and returns whether it was
+    /// found.
+    ///
+    /// Only remove items that were inserted: removing another item
+    /// with the same tag and buckets introduces false negatives.
+    pub fn remove<T: std::hash::Hash + ?Sized>(&mut self, item: &T) -> bool {
+        let fprint = self.params().fingerprint(item);
+
+        self.remove_fingerprint(fprint)
+    }
+
+    /// Removes the item with [`Fingerprint`] `fprint` from the filter,
+    /// and returns whether it was found.  The fingerprint must have
+    /// been computed with this filter's [`Params`].
+    pub fn remove_fingerprint(&mut self, fprint: Fingerprint) -> bool {
+        let tag = tag(fprint);
+        let index = self.index(fprint);
+        let alt = self.alt_index(index, tag);
+
+        if matches!(self.victim, Some((i, t)) if t == tag && (i == index || i == alt)) {
+            self.victim = None;
+            self.len -= 1;
+            return true;
+        }
+
+        for bucket in [index, alt] {
+            if let Some(slot) = self.buckets[bucket as usize]
+                .iter_mut()
+                .find(|slot| **slot == tag)
+            {
+                *slot = EMPTY;
+                self.len -= 1;
+
+                // Make room for the stashed victim, if any.
+                if let Some((index, tag)) = self.victim.take() {
+                    self.place(index, tag);
+                }
+
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Removes all items from the filter.
+    pub fn clear(&mut self) {
+        self.buckets
+            .iter_mut()
+            .for_each(|bucket| *bucket = [EMPTY; BUCKET_SIZE]);
+        self.len = 0;
+        self.victim = None;
+    }
+
+    /// Serialises the filter to bytes, including the
+    /// [`ParamsId`](crate::ParamsId) of the filter's [`Params`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut encoder = Encoder::new(MAGIC, VERSION, self.params().id());
+        let (victim_index, victim_tag) = self.victim.unwrap_or((0, EMPTY));
+
+        encoder
+            .u32(self.buckets.len() as u32)
+            .u64(self.len)
+            .u64(self.rng)
+            .u32(victim_index)
+            .u16(victim_tag);
+        for slot in self.buckets.iter().flatten() {
+            encoder.u16(*slot);
+        }
+
+        encoder.finish()
+    }
+
+    /// Deserialises a filter serialised by [`CuckooFilter::to_bytes`].
+    ///
+    /// Fails if `params` is not the [`Params`] that built the filter.
+    pub fn from_bytes(params: P, bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut decoder = Decoder::new(bytes, MAGIC, VERSION, params.borrow().id())?;
+        let num_buckets = decoder.u32()?;
+        let len = decoder.u64()?;
+        let rng = decoder.u64()?;
+        let victim_index = decoder.u32()?;
+        let victim_tag = decoder.u16()?;
+
+        if !num_buckets.is_power_of_two() {
+            return Err(DecodeError::Invalid("cuckoo filter size not a power of 2"));
+        }
+
+        if rng == 0 || victim_index >= num_buckets {
+            return Err(DecodeError::Invalid("invalid cuckoo filter state"));
+        }
+
+        if decoder.remaining() as u64 != 2 * (BUCKET_SIZE as u64) * num_buckets as u64 {
+            return Err(DecodeError::Invalid("cuckoo filter size mismatch"));
+        }
+
+        let mut buckets = vec![[EMPTY; BUCKET_SIZE]; num_buckets as usize];
+        for slot in buckets.iter_mut().flatten() {
+            *slot = decoder.u16()?;
+        }
+        decoder.finish()?;
+
+        let victim = if victim_tag != EMPTY {
+            Some((victim_index, victim_tag))
+        } else {
+            None
+        };
+        let stored = buckets
+            .iter()
+            .flatten()
+            .filter(|slot| **slot != EMPTY)
+            .count() as u64
+            + victim.is_some() as u64;
+        if stored != len {
+            return Err(DecodeError::Invalid("cuckoo filter length mismatch"));
+        }
+
+        Ok(CuckooFilter {
+            params,
+            buckets,
+            len,
+            victim,
+            rng,
+        })
+    }
+}
+
+/// See the [crate docs](crate#data-structures) for the
+/// [`Debug`](std::fmt::Debug) representation of data structures.
+impl<P: Borrow<Params>> std::fmt::Debug for CuckooFilter<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CuckooFilter")
+            .field("params", &self.params().id())
+            .field("len", &self.len)
+            .field("capacity", &self.capacity())
+            .field("full", &self.victim.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cuckoo::CuckooFilter;
+    use crate::{DecodeError, Params};
+
+    #[test]
+    fn test_insert_remove() {
+        let params = Params::derive(0, b"cuckoo filter");
+        let mut filter = CuckooFilter::new(&params, 10_000);
+
+        assert!(filter.is_empty());
+        for i in 0..10_000u32 {
+            assert!(filter.insert(&i));
+        }
+
+        assert_eq!(filter.len(), 10_000);
+        assert!((0..10_000u32).all(|i| filter.contains(&i)));
+
+        let false_positives = (10_000..110_000u32).filter(|i| filter.contains(i)).count();
+        // We expect ~50 false positives with 2^14 slots.
+        assert!(false_positives < 200, "{} false positives", false_positives);
+
+        for i in 0..5_000u32 {
+            assert!(filter.remove(&i));
+        }
+
+        assert_eq!(filter.len(), 5_000);
+        assert!((5_000..10_000u32).all(|i| filter.contains(&i)));
+        assert!((0..5_000u32).filter(|i| filter.contains(i)).count() < 100);
+
+        filter.clear();
+        assert!(filter.is_empty());
+        assert!(!filter.contains(&5_000u32));
+    }
+
+    #[test]
+    fn test_full() {
+        let params = Params::derive(0, b"cuckoo filter");
+        let mut filter = CuckooFilter::new(&params, 16);
+        let mut inserted = 0u32;
+
+        while filter.insert(&inserted) {
+            inserted += 1;
+        }
+
+        assert!(inserted as usize <= filter.capacity() + 1);
+        assert!(inserted as usize >= filter.capacity() / 2);
+        // No false negatives, even with the victim stash.
+        assert!((0..inserted).all(|i| filter.contains(&i)));
+
+        // Removing an item makes room again.
+        assert!(filter.remove(&0u32));
+        assert!((1..inserted).all(|i| filter.contains(&i)));
+        assert!(filter.insert(&0u32));
+    }
+
+    #[test]
+    fn test_bytes() {
+        let params = Params::derive(0, b"cuckoo filter");
+        let mut filter = CuckooFilter::new(&params, 100);
+        let fprint = params.fingerprinter(0).write(b"foo").digest();
+
+        filter.insert_fingerprint(fprint);
+        filter.insert(&"bar");
+
+        let bytes = filter.to_bytes();
+        let derived = Params::derive(0, b"cuckoo filter");
+        let mut copy = CuckooFilter::from_bytes(&derived, &bytes).expect("valid encoding");
+        assert_eq!(copy.len(), 2);
+        assert!(copy.contains_fingerprint(fprint));
+        assert!(copy.contains(&"bar"));
+        assert!(copy.remove_fingerprint(fprint));
+
+        assert_eq!(
+            CuckooFilter::from_bytes(&params, &bytes[..bytes.len() - 1]).err(),
+            Some(DecodeError::Invalid("cuckoo filter size mismatch"))
+        );
+
+        let other = Params::derive(1, b"cuckoo filter");
+        assert_eq!(
+            CuckooFilter::from_bytes(&other, &bytes).err(),
+            Some(DecodeError::ParamsMismatch {
+                expected: other.id(),
+                found: params.id()
+            })
+        );
+    }
+}
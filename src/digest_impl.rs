@@ -0,0 +1,157 @@
+//! Implementations of the RustCrypto [`digest`] traits, behind the
+//! `digest` feature.
+//!
+//! UMASH is *not* a cryptographic hash function: its collision bounds
+//! only hold for inputs that are independent of the [`Params`], and
+//! anyone who knows the [`Params`] can trivially construct collisions.
+//! That's why neither [`Hasher`] nor [`Fingerprinter`] implement
+//! [`digest::HashMarker`] (and thus [`digest::Digest`]); they only
+//! implement the lower-level [`Update`], [`FixedOutput`], [`Reset`]
+//! and [`OutputSizeUser`] traits, for use in generic checksumming
+//! code.
+//!
+//! The output bytes are the big-endian representation of the 64-bit
+//! hash value, or of the two 64-bit halves of the [`Fingerprint`].
+#[cfg(doc)]
+use crate::{Fingerprint, Params};
+use crate::{Fingerprinter, Hasher};
+use digest::consts::{U16, U8};
+use digest::{FixedOutput, FixedOutputReset, Output, OutputSizeUser, Reset, Update};
+
+/// A 64-bit [`Hasher`] outputs 8 bytes.
+impl OutputSizeUser for Hasher<'_> {
+    type OutputSize = U8;
+}
+
+impl Update for Hasher<'_> {
+    #[inline(always)]
+    fn update(&mut self, data: &[u8]) {
+        self.write(data);
+    }
+}
+
+impl FixedOutput for Hasher<'_> {
+    #[inline(always)]
+    fn finalize_into(self, out: &mut Output<Self>) {
+        out.copy_from_slice(&self.digest().to_be_bytes());
+    }
+}
+
+/// Resetting a [`Hasher`] brings it back to its initial state, for the
+/// same [`Params`], component, and seed.
+impl Reset for Hasher<'_> {
+    #[inline(always)]
+    fn reset(&mut self) {
+        Hasher::reset(self);
+    }
+}
+
+impl FixedOutputReset for Hasher<'_> {
+    #[inline(always)]
+    fn finalize_into_reset(&mut self, out: &mut Output<Self>) {
+        out.copy_from_slice(&self.digest().to_be_bytes());
+        Hasher::reset(self);
+    }
+}
+
+/// A 128-bit [`Fingerprinter`] outputs 16 bytes.
+impl OutputSizeUser for Fingerprinter<'_> {
+    type OutputSize = U16;
+}
+
+impl Update for Fingerprinter<'_> {
+    #[inline(always)]
+    fn update(&mut self, data: &[u8]) {
+        self.write(data);
+    }
+}
+
+impl FixedOutput for Fingerprinter<'_> {
+    #[inline(always)]
+    fn finalize_into(self, out: &mut Output<Self>) {
+        let fprint = self.digest();
+
+        out[..8].copy_from_slice(&fprint.hash().to_be_bytes());
+        out[8..].copy_from_slice(&fprint.secondary().to_be_bytes());
+    }
+}
+
+/// Resetting a [`Fingerprinter`] brings it back to its initial state,
+/// for the same [`Params`] and seed.
+impl Reset for Fingerprinter<'_> {
+    #[inline(always)]
+    fn reset(&mut self) {
+        Fingerprinter::reset(self);
+    }
+}
+
+impl FixedOutputReset for Fingerprinter<'_> {
+    #[inline(always)]
+    fn finalize_into_reset(&mut self, out: &mut Output<Self>) {
+        let fprint = self.digest();
+
+        out[..8].copy_from_slice(&fprint.hash().to_be_bytes());
+        out[8..].copy_from_slice(&fprint.secondary().to_be_bytes());
+        Fingerprinter::reset(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Params;
+    use digest::{FixedOutput, FixedOutputReset, Update};
+
+    // Generic code written against the `digest` traits.
+    fn checksum<D: Update + FixedOutput>(mut digest: D, chunks: &[&[u8]]) -> Vec<u8> {
+        for chunk in chunks {
+            digest.update(chunk);
+        }
+
+        digest.finalize_fixed().to_vec()
+    }
+
+    #[test]
+    fn test_example_case() {
+        let params = Params::derive(0, b"hello example.c");
+        let chunks: &[&[u8]] = &[b"the quick", b" brown fox"];
+
+        assert_eq!(
+            checksum(params.hasher(42), chunks),
+            0x398c5bb5cc113d03u64.to_be_bytes()
+        );
+        assert_eq!(
+            checksum(params.secondary_hasher(42), chunks),
+            0x3a52693519575aba_u64.to_be_bytes()
+        );
+        assert_eq!(
+            checksum(params.fingerprinter(42), chunks),
+            0x398c5bb5cc113d03_3a52693519575aba_u128.to_be_bytes()
+        );
+    }
+
+    #[test]
+    fn test_reset() {
+        let params = Params::derive(0, b"hello example.c");
+        let mut hasher = params.hasher(42);
+        let mut fprint = params.fingerprinter(42);
+
+        hasher.update(b"garbage");
+        fprint.update(b"garbage");
+        assert_ne!(
+            hasher.finalize_fixed_reset().as_slice(),
+            0x398c5bb5cc113d03u64.to_be_bytes()
+        );
+        fprint.finalize_fixed_reset();
+
+        hasher.update(b"the quick brown fox");
+        fprint.update(b"the quick brown fox");
+        assert_eq!(
+            hasher.finalize_fixed().as_slice(),
+            0x398c5bb5cc113d03u64.to_be_bytes()
+        );
+        assert_eq!(
+            fprint.finalize_fixed().as_slice(),
+            0x398c5bb5cc113d03_3a52693519575aba_u128.to_be_bytes()
+        );
+    }
+}
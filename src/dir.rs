@@ -0,0 +1,410 @@
+//! Fingerprints for whole directory trees, e.g., to key caches on
+//! build outputs.
+//!
+//! [`fingerprint_dir`] walks a directory tree, fingerprints each
+//! regular file's contents with a [`Fingerprinter`](crate::Fingerprinter)
+//! (`seed = 0`), and lists the files in a [`ManifestEntry`] per file,
+//! sorted by the bytes of their `/`-separated relative path.  The root
+//! [`Fingerprint`] covers the manifest, in that canonical order: each
+//! entry's kind, relative path, and content fingerprint and length,
+//! and, depending on the [`DirOptions`], its permission bits and
+//! symlink target.  The root is thus independent of the order in
+//! which the filesystem lists entries, and of timestamps and owners.
+//!
+//! Empty directories do not contribute to the root fingerprint.
+use crate::{Fingerprint, Params};
+use std::path::{Path, PathBuf};
+
+// Versions the byte stream for the root fingerprint.
+const FORMAT_VERSION: u8 = 1;
+
+/// How [`fingerprint_dir`] handles symbolic links.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Symlinks {
+    /// Record each symlink as an [`EntryKind::Symlink`] entry, and
+    /// fingerprint its target path, without following it.
+    Target,
+
+    /// Follow symlinks, and fingerprint what they point to as if it
+    /// were in the tree.  Fails on symlink cycles.
+    Follow,
+
+    /// Ignore symlinks.
+    Skip,
+}
+
+/// Options for [`fingerprint_dir`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DirOptions {
+    /// Whether to include each entry's permission bits (e.g., whether
+    /// a file is executable) in the root fingerprint.
+    pub include_modes: bool,
+
+    /// How to handle symbolic links.
+    pub symlinks: Symlinks,
+}
+
+/// By default, [`fingerprint_dir`] ignores permission bits, and
+/// records symlink targets without following them.
+impl Default for DirOptions {
+    fn default() -> Self {
+        DirOptions {
+            include_modes: false,
+            symlinks: Symlinks::Target,
+        }
+    }
+}
+
+/// The kind of a [`ManifestEntry`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum EntryKind {
+    /// A regular file, fingerprinted by contents.
+    File = 0,
+
+    /// A symlink, fingerprinted by target path.
+    Symlink = 1,
+}
+
+/// A [`ManifestEntry`] describes one file in a [`DirFingerprint`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ManifestEntry {
+    /// The path relative to the root directory.
+    pub path: PathBuf,
+
+    /// Whether the entry is a regular file or a symlink.
+    pub kind: EntryKind,
+
+    /// The permission bits, if [`DirOptions::include_modes`] is set.
+    pub mode: Option<u32>,
+
+    /// The number of bytes in the file, or in the symlink's target path.
+    pub len: u64,
+
+    /// The [`Fingerprint`] of the file contents, or of the symlink's
+    /// target path.
+    pub fingerprint: Fingerprint,
+}
+
+/// The result of [`fingerprint_dir`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DirFingerprint {
+    /// The fingerprint for the whole tree.
+    pub root: Fingerprint,
+
+    /// One entry per file (and symlink), sorted by relative path bytes.
+    pub manifest: Vec<ManifestEntry>,
+}
+
+/// Returns the bytes for `path`, with `/` as the separator.
+fn path_bytes(path: &Path) -> Vec<u8> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+
+        path.as_os_str().as_bytes().to_vec()
+    }
+
+    #[cfg(not(unix))]
+    {
+        path.to_string_lossy().replace('\\', "/").into_bytes()
+    }
+}
+
+/// Returns the permission bits in `metadata`.
+fn mode(metadata: &std::fs::Metadata) -> u32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        metadata.permissions().mode() & 0o7777
+    }
+
+    #[cfg(not(unix))]
+    {
+        if metadata.permissions().readonly() {
+            0o444
+        } else {
+            0o644
+        }
+    }
+}
+
+struct Walker<'a> {
+    params: &'a Params,
+    options: DirOptions,
+    // Canonical paths of the directories being walked, to detect
+    // symlink cycles.
+    ancestors: Vec<PathBuf>,
+    entries: Vec<(Vec<u8>, ManifestEntry)>,
+}
+
+impl Walker<'_> {
+    fn walk(&mut self, dir: &Path, relative: &Path) -> std::io::Result<()> {
+        let canonical = std::fs::canonicalize(dir)?;
+
+        if self.ancestors.contains(&canonical) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("symlink cycle at {}", dir.display()),
+            ));
+        }
+
+        self.ancestors.push(canonical);
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let relative = relative.join(entry.file_name());
+            let mut metadata = std::fs::symlink_metadata(&path)?;
+
+            if metadata.file_type().is_symlink() {
+                match self.options.symlinks {
+                    Symlinks::Skip => continue,
+                    Symlinks::Target => {
+                        let target = path_bytes(&std::fs::read_link(&path)?);
+                        let fingerprint = self.params.fingerprinter(0).write(&target).digest();
+
+                        self.push(
+                            relative,
+                            EntryKind::Symlink,
+                            &metadata,
+                            target.len() as u64,
+                            fingerprint,
+                        );
+                        continue;
+                    }
+                    Symlinks::Follow => metadata = std::fs::metadata(&path)?,
+                }
+            }
+
+            if metadata.is_dir() {
+                self.walk(&path, &relative)?;
+            } else if metadata.is_file() {
+                let mut fingerprinter = self.params.fingerprinter(0);
+                let len = std::io::copy(&mut std::fs::File::open(&path)?, &mut fingerprinter)?;
+                let fingerprint = fingerprinter.digest();
+
+                self.push(relative, EntryKind::File, &metadata, len, fingerprint);
+            }
+            // Ignore sockets, devices, etc.
+        }
+
+        self.ancestors.pop();
+        Ok(())
+    }
+
+    fn push(
+        &mut self,
+        path: PathBuf,
+        kind: EntryKind,
+        metadata: &std::fs::Metadata,
+        len: u64,
+        fingerprint: Fingerprint,
+    ) {
+        let entry = ManifestEntry {
+            kind,
+            mode: self.options.include_modes.then(|| mode(metadata)),
+            len,
+            fingerprint,
+            path,
+        };
+
+        self.entries.push((path_bytes(&entry.path), entry));
+    }
+}
+
+/// Fingerprints the directory tree under `path`, and returns the root
+/// [`Fingerprint`] along with a manifest of all the files in the tree.
+///
+/// Two trees with the same relative paths, file contents, and,
+/// depending on `options`, permission bits and symlink targets, have
+/// the same root fingerprint.
+pub fn fingerprint_dir(
+    params: &Params,
+    path: impl AsRef<Path>,
+    options: DirOptions,
+) -> std::io::Result<DirFingerprint> {
+    let path = path.as_ref();
+
+    if !std::fs::metadata(path)?.is_dir() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("{} is not a directory", path.display()),
+        ));
+    }
+
+    let mut walker = Walker {
+        params,
+        options,
+        ancestors: Vec::new(),
+        entries: Vec::new(),
+    };
+    walker.walk(path, Path::new(""))?;
+    walker.entries.sort_by(|x, y| x.0.cmp(&y.0));
+
+    let mut fingerprinter = params.fingerprinter(0);
+    fingerprinter.write(&[FORMAT_VERSION, options.include_modes as u8]);
+    for (bytes, entry) in walker.entries.iter() {
+        fingerprinter
+            .write(&[entry.kind as u8])
+            .write(&(bytes.len() as u64).to_le_bytes())
+            .write(bytes)
+            .write(&entry.len.to_le_bytes())
+            .write(&entry.fingerprint.hash[0].to_le_bytes())
+            .write(&entry.fingerprint.hash[1].to_le_bytes());
+        if let Some(mode) = entry.mode {
+            fingerprinter.write(&mode.to_le_bytes());
+        }
+    }
+
+    Ok(DirFingerprint {
+        root: fingerprinter.digest(),
+        manifest: walker.entries.into_iter().map(|(_, entry)| entry).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dir::{fingerprint_dir, DirOptions, EntryKind, Symlinks};
+    use crate::test_util::scratch_dir;
+    use crate::Params;
+    use std::path::{Path, PathBuf};
+
+    fn write(root: &Path, relative: &str, contents: &[u8]) {
+        let path = root.join(relative);
+
+        std::fs::create_dir_all(path.parent().unwrap()).expect("must create dirs");
+        std::fs::write(path, contents).expect("must write");
+    }
+
+    #[test]
+    fn test_canonical_order() {
+        let params = Params::derive(0, b"dir");
+        let left = scratch_dir("dir-order-left");
+        let right = scratch_dir("dir-order-right");
+
+        // Same tree, created in different orders.
+        write(&left, "a/b", b"ab");
+        write(&left, "a.txt", b"a");
+        write(&left, "z/y/x", b"zyx");
+        write(&right, "z/y/x", b"zyx");
+        write(&right, "a.txt", b"a");
+        write(&right, "a/b", b"ab");
+        std::fs::create_dir_all(right.join("empty")).expect("must create");
+
+        let options = DirOptions::default();
+        let fprint = fingerprint_dir(&params, &left, options).expect("must succeed");
+        assert_eq!(
+            fprint,
+            fingerprint_dir(&params, &right, options).expect("must succeed")
+        );
+
+        // Byte order: '.' sorts before '/'.
+        let paths: Vec<_> = fprint
+            .manifest
+            .iter()
+            .map(|entry| entry.path.clone())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("a.txt"),
+                PathBuf::from("a/b"),
+                PathBuf::from("z/y/x")
+            ]
+        );
+        assert_eq!(fprint.manifest[0].kind, EntryKind::File);
+        assert_eq!(fprint.manifest[0].len, 1);
+        assert_eq!(
+            fprint.manifest[0].fingerprint,
+            params.fingerprinter(0).write(b"a").digest()
+        );
+
+        // Contents and paths both matter.
+        write(&right, "a/b", b"AB");
+        let changed = fingerprint_dir(&params, &right, options).expect("must succeed");
+        assert_ne!(changed.root, fprint.root);
+        assert_eq!(changed.manifest[0], fprint.manifest[0]);
+        assert_ne!(changed.manifest[1], fprint.manifest[1]);
+
+        write(&right, "a/b", b"ab");
+        std::fs::rename(right.join("a.txt"), right.join("b.txt")).expect("must rename");
+        assert_ne!(
+            fingerprint_dir(&params, &right, options)
+                .expect("must succeed")
+                .root,
+            fprint.root
+        );
+
+        assert!(fingerprint_dir(&params, left.join("a.txt"), options).is_err());
+
+        let _ = std::fs::remove_dir_all(&left);
+        let _ = std::fs::remove_dir_all(&right);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_modes_and_symlinks() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let params = Params::derive(0, b"dir");
+        let dir = scratch_dir("dir-unix");
+        write(&dir, "bin/tool", b"#!/bin/sh\n");
+        write(&dir, "data/file", b"data");
+        std::os::unix::fs::symlink("../data", dir.join("bin/data")).expect("must symlink");
+
+        let with_modes = DirOptions {
+            include_modes: true,
+            ..DirOptions::default()
+        };
+        let before = fingerprint_dir(&params, &dir, DirOptions::default()).expect("must succeed");
+        let before_modes = fingerprint_dir(&params, &dir, with_modes).expect("must succeed");
+
+        std::fs::set_permissions(dir.join("bin/tool"), std::fs::Permissions::from_mode(0o755))
+            .expect("must chmod");
+        assert_eq!(
+            fingerprint_dir(&params, &dir, DirOptions::default())
+                .expect("must succeed")
+                .root,
+            before.root
+        );
+        let after_modes = fingerprint_dir(&params, &dir, with_modes).expect("must succeed");
+        assert_ne!(after_modes.root, before_modes.root);
+        assert_eq!(after_modes.manifest[1].mode, Some(0o755));
+
+        // By default, symlinks are recorded by target.
+        assert_eq!(before.manifest.len(), 3);
+        assert_eq!(before.manifest[0].path, PathBuf::from("bin/data"));
+        assert_eq!(before.manifest[0].kind, EntryKind::Symlink);
+        assert_eq!(
+            before.manifest[0].fingerprint,
+            params.fingerprinter(0).write(b"../data").digest()
+        );
+
+        let follow = DirOptions {
+            symlinks: Symlinks::Follow,
+            ..DirOptions::default()
+        };
+        let followed = fingerprint_dir(&params, &dir, follow).expect("must succeed");
+        assert_eq!(followed.manifest.len(), 3);
+        assert_eq!(followed.manifest[0].path, PathBuf::from("bin/data/file"));
+        assert_eq!(followed.manifest[0].kind, EntryKind::File);
+
+        let skip = DirOptions {
+            symlinks: Symlinks::Skip,
+            ..DirOptions::default()
+        };
+        assert_eq!(
+            fingerprint_dir(&params, &dir, skip)
+                .expect("must succeed")
+                .manifest
+                .len(),
+            2
+        );
+
+        // Following a cycle fails.
+        std::os::unix::fs::symlink("..", dir.join("data/loop")).expect("must symlink");
+        assert!(fingerprint_dir(&params, &dir, follow).is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
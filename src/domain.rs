@@ -0,0 +1,160 @@
+//! A [`Domain`] derives independent [`Params`] for each purpose
+//! (cache keys, shard routing, deduplication, ...) from a single root
+//! key.
+//!
+//! Tweaking the `seed` argument of [`Params::hasher`] or
+//! [`Params::fingerprinter`] yields different hash values, but UMASH
+//! offers no collision bound across seeds.  Deriving a fresh set of
+//! [`Params`] per purpose does: each label maps to its own `bits`
+//! value and key for [`Params::derive`], so the resulting UMASH
+//! functions are independent of one another, and of the [`Params`]
+//! one would derive directly from the root key.
+use crate::Params;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+// The `bits` values for the labelers derived from the root key.
+const BITS_LABELER: u64 = 0x646f_6d61_696e_0001;
+const KEY_LABELER: u64 = 0x646f_6d61_696e_0002;
+
+/// A [`Domain`] maps string labels to [`Params`] derived from a root
+/// key, and caches the result for each label.
+///
+/// The [`Params`] for a given `(key, label)` pair are deterministic:
+/// they will be the same across processes, programs, architectures,
+/// and UMASH versions, as long as the root key and the label are
+/// the same.
+pub struct Domain {
+    // Derives the `bits` argument for each label.
+    bits_labeler: Params,
+    // Derives the `key` argument for each label.
+    key_labeler: Params,
+    registry: Mutex<HashMap<String, Arc<Params>>>,
+}
+
+impl Domain {
+    /// Returns a fresh [`Domain`] for the first 32 bytes in `key`.
+    /// Shorter keys are padded with zero bytes, as in
+    /// [`Params::derive`].
+    pub fn new(key: &[u8]) -> Self {
+        Domain {
+            bits_labeler: Params::derive(BITS_LABELER, key),
+            key_labeler: Params::derive(KEY_LABELER, key),
+            registry: Default::default(),
+        }
+    }
+
+    /// Returns the `bits` value passed to [`Params::derive`] for
+    /// `label`'s [`Params`].
+    pub fn bits(&self, label: &str) -> u64 {
+        self.bits_labeler.hasher(0).write(label.as_bytes()).digest()
+    }
+
+    /// Returns the [`Params`] for `label`, derived from the root key
+    /// the first time the label is seen, and cached afterwards.
+    pub fn params(&self, label: &str) -> Arc<Params> {
+        let mut registry = self.registry.lock().unwrap_or_else(|e| e.into_inner());
+
+        if let Some(params) = registry.get(label) {
+            return params.clone();
+        }
+
+        let mut key = [0u8; 32];
+        self.key_labeler.fill_key(label.as_bytes(), &mut key);
+        let params = Arc::new(Params::derive(self.bits(label), &key));
+
+        #[cfg(feature = "zeroize")]
+        zeroize::Zeroize::zeroize(&mut key);
+
+        registry.insert(label.to_owned(), params.clone());
+        params
+    }
+
+    /// Returns the labels for which [`Domain::params`] has been
+    /// called so far, in arbitrary order.
+    pub fn labels(&self) -> Vec<String> {
+        let registry = self.registry.lock().unwrap_or_else(|e| e.into_inner());
+
+        registry.keys().cloned().collect()
+    }
+}
+
+/// The [`Debug`](std::fmt::Debug) representation of a [`Domain`]
+/// lists the labels seen so far, and redacts the root key.
+impl std::fmt::Debug for Domain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Domain")
+            .field("labels", &self.labels())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Domain, Params};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_cached_params() {
+        let domain = Domain::new(b"root key");
+        let shard = domain.params("shard");
+
+        assert!(Arc::ptr_eq(&shard, &domain.params("shard")));
+        assert_eq!(domain.labels(), vec!["shard".to_string()]);
+    }
+
+    #[test]
+    fn test_labels_are_separated() {
+        let domain = Domain::new(b"root key");
+        let cache = domain.params("cache");
+        let dedup = domain.params("dedup");
+
+        assert_ne!(domain.bits("cache"), domain.bits("dedup"));
+        assert_ne!(cache.hash(b"foo"), dedup.hash(b"foo"));
+        assert_ne!(cache.fingerprint(b"foo"), dedup.fingerprint(b"foo"));
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let domain = Domain::new(b"root key");
+        let expected = domain.params("cache").fingerprint(b"foo");
+
+        assert_eq!(
+            Domain::new(b"root key").params("cache").fingerprint(b"foo"),
+            expected
+        );
+        assert_ne!(
+            Domain::new(b"other key")
+                .params("cache")
+                .fingerprint(b"foo"),
+            expected
+        );
+
+        // Keys longer than 32 bytes are truncated, as for
+        // `Params::derive`.
+        let long_key = [7u8; 40];
+        assert_eq!(
+            Domain::new(&long_key).params("cache").fingerprint(b"foo"),
+            Domain::new(&long_key[..32])
+                .params("cache")
+                .fingerprint(b"foo")
+        );
+    }
+
+    #[test]
+    fn test_root_key_not_reused() {
+        let domain = Domain::new(b"root key");
+        let bits = domain.bits("cache");
+
+        // The label's params are not those a user would derive from
+        // the root key with the same `bits`.
+        assert_ne!(
+            domain.params("cache").fingerprint(b"foo"),
+            Params::derive(bits, b"root key").fingerprint(b"foo")
+        );
+        assert_ne!(
+            domain.bits("cache"),
+            Params::derive(0, b"root key").hash(b"cache")
+        );
+    }
+}
@@ -0,0 +1,269 @@
+//! Finds duplicate files, with the primary/secondary split described
+//! in the [`Fingerprint`] docs.
+//!
+//! [`find_duplicates`] walks directory trees and narrows down the set
+//! of candidate duplicates in stages, from cheapest to most expensive:
+//!
+//! 1. group regular files by size;
+//! 2. group files of the same size by the primary
+//!    [`UmashComponent::Hash`](crate::UmashComponent::Hash) of their
+//!    first [`FIRST_BLOCK_SIZE`] bytes;
+//! 3. group the remaining candidates by the [`Fingerprint`] of their
+//!    full contents.
+//!
+//! Only the last stage reads whole files, and only for files that
+//! already share a size and a prefix hash.  Files that are at most
+//! [`FIRST_BLOCK_SIZE`] bytes long skip the second stage.
+//!
+//! Unless the [`Params`] are secret (e.g., from [`Params::new`]),
+//! someone who controls the files' contents could craft distinct files
+//! that are reported as duplicates.  Compare the bytes before deleting
+//! anything that matters.
+use crate::{Fingerprint, Params};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// The number of bytes in the prefix that [`find_duplicates`] hashes
+/// before fingerprinting whole files.
+pub const FIRST_BLOCK_SIZE: usize = 4096;
+
+/// A [`DuplicateGroup`] is a set of at least two files with the same
+/// size and contents.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DuplicateGroup {
+    /// The size of each file, in bytes.
+    pub len: u64,
+
+    /// The [`Fingerprint`] (`seed = 0`) of each file's contents.
+    pub fingerprint: Fingerprint,
+
+    /// The paths of the duplicate files, sorted.
+    pub paths: Vec<PathBuf>,
+}
+
+/// The result of [`find_duplicates`]: the duplicate files, and the
+/// paths that could not be read.
+#[derive(Debug, Default)]
+pub struct Duplicates {
+    /// The groups of duplicate files, sorted by decreasing file size,
+    /// then by path.
+    pub groups: Vec<DuplicateGroup>,
+
+    /// The paths that could not be listed or read, with the error for
+    /// each.  These paths are skipped, and do not appear in `groups`.
+    pub errors: Vec<(PathBuf, std::io::Error)>,
+}
+
+/// Appends every regular file under `path` to `files`, without
+/// following symlinks.  Paths that can't be listed go to `errors`.
+fn collect_files(
+    path: &Path,
+    files: &mut Vec<(u64, PathBuf)>,
+    errors: &mut Vec<(PathBuf, std::io::Error)>,
+) {
+    let metadata = match std::fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) => return errors.push((path.to_owned(), e)),
+    };
+
+    if metadata.is_dir() {
+        let entries = match std::fs::read_dir(path) {
+            Ok(entries) => entries,
+            Err(e) => return errors.push((path.to_owned(), e)),
+        };
+
+        for entry in entries {
+            match entry {
+                Ok(entry) => collect_files(&entry.path(), files, errors),
+                Err(e) => errors.push((path.to_owned(), e)),
+            }
+        }
+    } else if metadata.is_file() {
+        files.push((metadata.len(), path.to_owned()));
+    }
+}
+
+/// Returns the primary hash of the first [`FIRST_BLOCK_SIZE`] bytes in
+/// the file at `path`.
+fn first_block_hash(params: &Params, path: &Path) -> std::io::Result<u64> {
+    let mut hasher = params.hasher(0);
+    let file = std::fs::File::open(path)?;
+
+    std::io::copy(&mut file.take(FIRST_BLOCK_SIZE as u64), &mut hasher)?;
+    Ok(hasher.digest())
+}
+
+/// Returns the fingerprint of the file at `path`.
+fn file_fingerprint(params: &Params, path: &Path) -> std::io::Result<Fingerprint> {
+    let mut fingerprinter = params.fingerprinter(0);
+
+    std::io::copy(&mut std::fs::File::open(path)?, &mut fingerprinter)?;
+    Ok(fingerprinter.digest())
+}
+
+/// Regroups each group of files of the same size in `groups` by `key`,
+/// and drops the resulting groups with fewer than two paths.  Paths
+/// for which `key` fails go to `errors`.
+fn refine<K: Eq + std::hash::Hash>(
+    groups: Vec<(u64, Vec<PathBuf>)>,
+    errors: &mut Vec<(PathBuf, std::io::Error)>,
+    mut key: impl FnMut(&Path) -> std::io::Result<K>,
+) -> Vec<(u64, K, Vec<PathBuf>)> {
+    let mut ret = Vec::new();
+
+    for (len, group) in groups {
+        let mut by_key: HashMap<K, Vec<PathBuf>> = HashMap::new();
+
+        for path in group {
+            match key(&path) {
+                Ok(key) => by_key.entry(key).or_default().push(path),
+                Err(e) => errors.push((path, e)),
+            }
+        }
+
+        ret.extend(
+            by_key
+                .into_iter()
+                .filter(|(_, paths)| paths.len() > 1)
+                .map(|(key, paths)| (len, key, paths)),
+        );
+    }
+
+    ret
+}
+
+/// Finds groups of regular files with identical contents under
+/// `roots` (directories or files).
+///
+/// Symlinks are ignored, and paths listed more than once (e.g., for
+/// overlapping roots) are only considered once; hard links to the
+/// same file are reported as duplicates.  Paths that can't be listed
+/// or read are skipped, and reported in [`Duplicates::errors`].
+pub fn find_duplicates(
+    params: &Params,
+    roots: impl IntoIterator<Item = impl AsRef<Path>>,
+) -> Duplicates {
+    let mut files = Vec::new();
+    let mut errors = Vec::new();
+
+    for root in roots {
+        collect_files(root.as_ref(), &mut files, &mut errors);
+    }
+
+    files.sort();
+    files.dedup();
+
+    let mut by_len: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+    for (len, path) in files {
+        by_len.entry(len).or_default().push(path);
+    }
+
+    let mut small = Vec::new();
+    let mut large = Vec::new();
+    for (len, paths) in by_len {
+        if paths.len() < 2 {
+            continue;
+        }
+
+        if len <= FIRST_BLOCK_SIZE as u64 {
+            small.push((len, paths));
+        } else {
+            large.push((len, paths));
+        }
+    }
+
+    let candidates = refine(large, &mut errors, |path| first_block_hash(params, path))
+        .into_iter()
+        .map(|(len, _, paths)| (len, paths))
+        .chain(small)
+        .collect();
+
+    let mut groups = Vec::new();
+    for (len, fingerprint, mut paths) in refine(candidates, &mut errors, |path| {
+        file_fingerprint(params, path)
+    }) {
+        paths.sort();
+        groups.push(DuplicateGroup {
+            len,
+            fingerprint,
+            paths,
+        });
+    }
+
+    groups.sort_by(|x, y| y.len.cmp(&x.len).then_with(|| x.paths.cmp(&y.paths)));
+    errors.sort_by(|x, y| x.0.cmp(&y.0));
+    Duplicates { groups, errors }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dupes::{find_duplicates, FIRST_BLOCK_SIZE};
+    use crate::test_util::scratch_dir;
+    use crate::Params;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_find_duplicates() {
+        let params = Params::derive(0, b"dupes");
+        let dir = scratch_dir("dupes");
+        std::fs::create_dir_all(dir.join("a/b")).expect("must create dirs");
+        std::fs::create_dir_all(dir.join("c")).expect("must create dirs");
+
+        let large = vec![7u8; 3 * FIRST_BLOCK_SIZE];
+        // Same size and first block, different tail.
+        let mut large_tail = large.clone();
+        *large_tail.last_mut().unwrap() = 8;
+        // Same size, different first block.
+        let mut large_head = large.clone();
+        large_head[0] = 8;
+
+        let files: &[(&str, &[u8])] = &[
+            ("a/small", b"hello"),
+            ("a/b/small", b"hello"),
+            ("c/small", b"hello"),
+            ("c/other", b"world"),
+            ("a/large", &large),
+            ("c/large", &large),
+            ("c/large_tail", &large_tail),
+            ("c/large_head", &large_head),
+            ("a/b/empty", b""),
+            ("c/empty", b""),
+            ("c/unique", b"unique"),
+        ];
+        for (path, contents) in files {
+            std::fs::write(dir.join(path), contents).expect("must write");
+        }
+
+        // Overlapping roots don't duplicate paths.
+        let duplicates = find_duplicates(&params, [dir.clone(), dir.join("c")]);
+        assert!(duplicates.errors.is_empty());
+        let groups = duplicates.groups;
+        let paths =
+            |names: &[&str]| -> Vec<PathBuf> { names.iter().map(|name| dir.join(name)).collect() };
+
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[0].len, large.len() as u64);
+        assert_eq!(groups[0].paths, paths(&["a/large", "c/large"]));
+        assert_eq!(
+            groups[0].fingerprint,
+            params.fingerprinter(0).write(&large).digest()
+        );
+        assert_eq!(groups[1].len, 5);
+        assert_eq!(groups[1].paths, paths(&["a/b/small", "a/small", "c/small"]));
+        assert_eq!(groups[2].len, 0);
+        assert_eq!(groups[2].paths, paths(&["a/b/empty", "c/empty"]));
+
+        assert_eq!(find_duplicates(&params, [dir.join("c")]).groups.len(), 0);
+
+        // Missing paths are reported, and don't stop the scan.
+        let duplicates = find_duplicates(&params, [dir.join("missing"), dir.join("a")]);
+        assert_eq!(duplicates.groups.len(), 1);
+        assert_eq!(duplicates.groups[0].paths, paths(&["a/b/small", "a/small"]));
+        assert_eq!(duplicates.errors.len(), 1);
+        assert_eq!(duplicates.errors[0].0, dir.join("missing"));
+        assert_eq!(duplicates.errors[0].1.kind(), std::io::ErrorKind::NotFound);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
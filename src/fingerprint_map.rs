@@ -0,0 +1,178 @@
+//! Hash maps and sets keyed by [`Fingerprint`]s, that use the
+//! fingerprint's bits as the table hash instead of hashing the key
+//! again.
+use crate::Fingerprint;
+use std::collections::{HashMap, HashSet};
+
+/// A [`HashMap`] keyed by [`Fingerprint`]s, with the
+/// [`FingerprintBuildHasher`].
+///
+/// Construct with `FingerprintMap::default()` or
+/// `FingerprintMap::with_capacity_and_hasher(n, Default::default())`.
+pub type FingerprintMap<V> = HashMap<Fingerprint, V, FingerprintBuildHasher>;
+
+/// A [`HashSet`] of [`Fingerprint`]s, with the
+/// [`FingerprintBuildHasher`].
+///
+/// Construct with `FingerprintSet::default()` or
+/// `FingerprintSet::with_capacity_and_hasher(n, Default::default())`.
+pub type FingerprintSet = HashSet<Fingerprint, FingerprintBuildHasher>;
+
+/// A [`FingerprintBuildHasher`] builds [`FingerprintHasher`]s, which
+/// return [`Fingerprint::hash`] as the hash value for a
+/// [`Fingerprint`].
+///
+/// This identity hash is only as good as the fingerprints: it's a
+/// fine table hash for [`Fingerprint`]s computed with secret
+/// [`Params`](crate::Params), e.g., from [`Params::new`](crate::Params::new).
+/// When attackers know the [`Params`](crate::Params) (e.g., derived
+/// from a public key), they can craft inputs with colliding
+/// [`Fingerprint::hash`] values, and degrade the table's performance.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct FingerprintBuildHasher;
+
+impl std::hash::BuildHasher for FingerprintBuildHasher {
+    type Hasher = FingerprintHasher;
+
+    #[inline(always)]
+    fn build_hasher(&self) -> FingerprintHasher {
+        FingerprintHasher::default()
+    }
+}
+
+/// A [`FingerprintHasher`] passes through the [`Fingerprint::hash`]
+/// value of the [`Fingerprint`] it hashes.
+///
+/// The hasher is meant for [`Fingerprint`] keys, and also passes
+/// through `u64` keys.  Only the first value written to the hasher can
+/// be passed through: either a single `u64`, or the 16 bytes of a
+/// [`Fingerprint`]'s `[u64; 2]` array (optionally preceded by its
+/// length, 2).  Everything else, including all the bytes of other
+/// keys, is mixed into the hash value with a simple byte-wise hash.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FingerprintHasher {
+    hash: u64,
+    // Whether any value other than a leading length prefix was
+    // written so far.
+    started: bool,
+}
+
+impl FingerprintHasher {
+    #[inline(always)]
+    fn mix(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.hash = (self.hash.rotate_left(5) ^ *byte as u64).wrapping_mul(0x9e3779b97f4a7c15);
+        }
+
+        self.started = true;
+    }
+}
+
+impl std::hash::Hasher for FingerprintHasher {
+    #[inline(always)]
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+
+    #[inline(always)]
+    fn write(&mut self, bytes: &[u8]) {
+        // `Fingerprint`'s derived `Hash` writes its `[u64; 2]` array
+        // in a single 16-byte call; the first 8 bytes are
+        // `Fingerprint::hash`.
+        if !self.started && bytes.len() == 16 {
+            let mut head = [0u8; 8];
+
+            head.copy_from_slice(&bytes[..8]);
+            self.hash = u64::from_ne_bytes(head);
+            self.started = true;
+        } else {
+            self.mix(bytes);
+        }
+    }
+
+    #[inline(always)]
+    fn write_u64(&mut self, value: u64) {
+        if !self.started {
+            self.hash = value;
+            self.started = true;
+        } else {
+            self.mix(&value.to_ne_bytes());
+        }
+    }
+
+    #[inline(always)]
+    fn write_usize(&mut self, value: usize) {
+        // Slices (including the `[u64; 2]` in `Fingerprint`) are
+        // prefixed with their length: skip that prefix for
+        // `Fingerprint`s, and mix everything else.
+        if self.started || value != 2 {
+            self.mix(&value.to_ne_bytes());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Fingerprint, FingerprintBuildHasher, FingerprintMap, FingerprintSet, Params};
+    use std::hash::{BuildHasher, Hash, Hasher};
+
+    fn hash_one(value: impl Hash) -> u64 {
+        let mut hasher = FingerprintBuildHasher.build_hasher();
+
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_identity_hash() {
+        let fprint = Fingerprint::new(0x0123456789abcdef, 42);
+
+        assert_eq!(hash_one(fprint), fprint.hash());
+        assert_eq!(hash_one(fprint.hash()), fprint.hash());
+        assert_ne!(hash_one("foo"), hash_one("bar"));
+    }
+
+    #[test]
+    fn test_mixes_other_keys() {
+        let long = "a long key that shares its first bytes";
+        let other = "a long key that shares its first bytes, but not all";
+
+        assert_ne!(hash_one(long), hash_one(other));
+        assert_ne!(hash_one(long.as_bytes()), hash_one(other.as_bytes()));
+        assert_ne!(hash_one([1u8; 16]), hash_one([1u8; 15]));
+        assert_ne!(
+            hash_one(&[1u8, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16][..]),
+            hash_one(&[1u8, 2, 3, 4, 5, 6, 7, 8, 0, 0, 0, 0, 0, 0, 0, 0][..])
+        );
+
+        // Only the first `u64` is passed through.
+        assert_ne!(hash_one((1u64, 2u64)), hash_one((1u64, 3u64)));
+        let fprint = Fingerprint::new(1, 2);
+        assert_ne!(hash_one((fprint, 3u32)), hash_one((fprint, 4u32)));
+    }
+
+    #[test]
+    fn test_map_and_set() {
+        let params = Params::derive(0, b"fingerprint map");
+        let mut map = FingerprintMap::default();
+        let mut set = FingerprintSet::with_capacity_and_hasher(1000, Default::default());
+
+        for i in 0..1000u32 {
+            let fprint = params.fingerprint(i);
+
+            map.insert(fprint, i);
+            assert!(set.insert(fprint));
+        }
+
+        assert!(!set.insert(params.fingerprint(10u32)));
+        assert_eq!(map.len(), 1000);
+        assert_eq!(map.get(&params.fingerprint(123u32)), Some(&123));
+        assert_eq!(map.get(&params.fingerprint(1000u32)), None);
+
+        // Fingerprints that only differ in their secondary hash are
+        // still distinct keys.
+        let fprint = params.fingerprint(0u32);
+        assert!(set.insert(Fingerprint::new(fprint.hash(), !fprint.secondary())));
+        assert_eq!(set.len(), 1001);
+    }
+}
@@ -0,0 +1,475 @@
+//! A HyperLogLog cardinality estimator keyed by UMASH hash values.
+//!
+//! Each item is hashed with the primary [`UmashComponent::Hash`](crate::UmashComponent::Hash)
+//! function of the estimator's [`Params`], for `seed = 0`: the top
+//! `precision` bits of the hash value select a register, and the
+//! number of leading zeros in the remaining bits determines the rank
+//! stored in that register.  With [`Params::derive`], the estimates
+//! are thus reproducible across processes and machines.
+//!
+//! Small estimators start in a sparse representation that only stores
+//! the non-zero registers, and switch to the dense array of `2^precision`
+//! registers once that's more compact.
+//!
+//! Pass hash values for raw bytes to [`HyperLogLog::insert_hash`]
+//! when estimators are merged across programs (see the
+//! [crate docs](crate#data-structures)).
+use crate::codec::{check_params, Decoder, Encoder};
+use crate::{DecodeError, MergeError, Params};
+use std::borrow::Borrow;
+
+const MAGIC: &[u8; 4] = b"UMHL";
+const VERSION: u8 = 1;
+
+/// The smallest supported precision.
+pub const MIN_PRECISION: u8 = 4;
+
+/// The largest supported precision.
+pub const MAX_PRECISION: u8 = 18;
+
+// Sparse entries pack the register index above a 6-bit rank.
+const RANK_BITS: u32 = 6;
+const RANK_MASK: u32 = (1 << RANK_BITS) - 1;
+
+#[derive(Clone)]
+enum Registers {
+    // Sorted by register index, without duplicate indices.
+    Sparse(Vec<u32>),
+    Dense(Vec<u8>),
+}
+
+/// A [`HyperLogLog`] estimates the number of distinct items inserted
+/// in it, with a relative standard error of about
+/// `1.04 / sqrt(2^precision)`.
+///
+/// See the [crate docs](crate#data-structures) for the choice of
+/// `P`.
+#[derive(Clone)]
+pub struct HyperLogLog<P: Borrow<Params>> {
+    params: P,
+    precision: u8,
+    registers: Registers,
+}
+
+impl<P: Borrow<Params>> HyperLogLog<P> {
+    /// Returns an empty [`HyperLogLog`] with `2^precision` registers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `precision` is not in
+    /// [`MIN_PRECISION`]`..=`[`MAX_PRECISION`].
+    pub fn new(params: P, precision: u8) -> Self {
+        assert!(
+            (MIN_PRECISION..=MAX_PRECISION).contains(&precision),
+            "HyperLogLog precision must be in [4, 18]"
+        );
+
+        HyperLogLog {
+            params,
+            precision,
+            registers: Registers::Sparse(Vec::new()),
+        }
+    }
+
+    /// Returns the [`Params`] for this estimator.
+    #[inline(always)]
+    pub fn params(&self) -> &Params {
+        self.params.borrow()
+    }
+
+    /// Returns the estimator's precision: it has `2^precision`
+    /// registers.
+    #[inline(always)]
+    pub fn precision(&self) -> u8 {
+        self.precision
+    }
+
+    /// Determines whether the estimator currently uses the sparse
+    /// representation.
+    #[inline(always)]
+    pub fn is_sparse(&self) -> bool {
+        matches!(self.registers, Registers::Sparse(_))
+    }
+
+    #[inline(always)]
+    fn num_registers(&self) -> usize {
+        1 << self.precision
+    }
+
+    /// Adds `item` to the estimator.
+    pub fn insert<T: std::hash::Hash + ?Sized>(&mut self, item: &T) {
+        let hash = self.params().hash(item);
+
+        self.insert_hash(hash)
+    }
+
+    /// Adds the item with primary hash value `hash` to the estimator.
+    /// The hash value must have been computed with this estimator's
+    /// [`Params`].
+    pub fn insert_hash(&mut self, hash: u64) {
+        let index = (hash >> (64 - self.precision)) as u32;
+        // The sentinel bit bounds the rank to `64 - precision + 1`.
+        let rank = ((hash << self.precision) | (1 << (self.precision - 1))).leading_zeros() + 1;
+
+        self.update(index, rank as u8);
+    }
+
+    fn update(&mut self, index: u32, rank: u8) {
+        let max_sparse = self.num_registers() / 4;
+
+        match &mut self.registers {
+            Registers::Dense(registers) => {
+                let register = &mut registers[index as usize];
+                *register = (*register).max(rank);
+            }
+            Registers::Sparse(entries) => {
+                match entries.binary_search_by_key(&index, |entry| entry >> RANK_BITS) {
+                    Ok(i) => {
+                        let old = (entries[i] & RANK_MASK) as u8;
+                        entries[i] = (index << RANK_BITS) | old.max(rank) as u32;
+                    }
+                    Err(i) => entries.insert(i, (index << RANK_BITS) | rank as u32),
+                }
+
+                // Switch to the dense representation when it's smaller.
+                if entries.len() > max_sparse {
+                    self.densify();
+                }
+            }
+        }
+    }
+
+    fn densify(&mut self) {
+        if let Registers::Sparse(entries) = &self.registers {
+            let mut registers = vec![0u8; self.num_registers()];
+
+            for entry in entries {
+                registers[(entry >> RANK_BITS) as usize] = (entry & RANK_MASK) as u8;
+            }
+
+            self.registers = Registers::Dense(registers);
+        }
+    }
+
+    /// Estimates the number of distinct items inserted in the
+    /// estimator.
+    pub fn estimate(&self) -> f64 {
+        let m = self.num_registers() as f64;
+        let (zeros, sum) = match &self.registers {
+            Registers::Sparse(entries) => {
+                let zeros = self.num_registers() - entries.len();
+                let sum = entries
+                    .iter()
+                    .map(|entry| (-((entry & RANK_MASK) as f64)).exp2())
+                    .sum::<f64>();
+
+                (zeros, sum + zeros as f64)
+            }
+            Registers::Dense(registers) => {
+                let zeros = registers.iter().filter(|rank| **rank == 0).count();
+                let sum = registers
+                    .iter()
+                    .map(|rank| (-(*rank as f64)).exp2())
+                    .sum::<f64>();
+
+                (zeros, sum)
+            }
+        };
+
+        let alpha = match self.precision {
+            4 => 0.673,
+            5 => 0.697,
+            6 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+        let raw = alpha * m * m / sum;
+
+        // Linear counting is more accurate for small cardinalities.
+        // There's no need for a large range correction with 64-bit
+        // hash values.
+        if raw <= 2.5 * m && zeros > 0 {
+            m * (m / zeros as f64).ln()
+        } else {
+            raw
+        }
+    }
+
+    /// Adds all the items in `other` to this estimator.  The result is
+    /// the same as if all the items in `other` had been inserted in
+    /// `self`.
+    ///
+    /// Fails without modifying `self` if the two estimators have
+    /// different [`Params`] or precisions.
+    pub fn merge<Q: Borrow<Params>>(&mut self, other: &HyperLogLog<Q>) -> Result<(), MergeError> {
+        check_params(self.params().id(), other.params().id())?;
+        if self.precision != other.precision {
+            return Err(MergeError::ShapeMismatch);
+        }
+
+        match &other.registers {
+            Registers::Sparse(entries) => {
+                for entry in entries {
+                    self.update(entry >> RANK_BITS, (entry & RANK_MASK) as u8);
+                }
+            }
+            Registers::Dense(other_registers) => {
+                self.densify();
+                if let Registers::Dense(registers) = &mut self.registers {
+                    for (dst, src) in registers.iter_mut().zip(other_registers.iter()) {
+                        *dst = (*dst).max(*src);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Removes all items from the estimator, and switches back to the
+    /// sparse representation.
+    pub fn clear(&mut self) {
+        self.registers = Registers::Sparse(Vec::new());
+    }
+
+    /// Serialises the estimator to bytes, including the
+    /// [`ParamsId`](crate::ParamsId) of the estimator's [`Params`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut encoder = Encoder::new(MAGIC, VERSION, self.params().id());
+
+        encoder.u8(self.precision);
+        match &self.registers {
+            Registers::Sparse(entries) => {
+                encoder.u8(0).u32(entries.len() as u32);
+                for entry in entries {
+                    encoder.u32(*entry);
+                }
+            }
+            Registers::Dense(registers) => {
+                encoder.u8(1).bytes(registers);
+            }
+        }
+
+        encoder.finish()
+    }
+
+    /// Deserialises an estimator serialised by
+    /// [`HyperLogLog::to_bytes`].
+    ///
+    /// Fails if `params` is not the [`Params`] that built the
+    /// estimator.
+    pub fn from_bytes(params: P, bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut decoder = Decoder::new(bytes, MAGIC, VERSION, params.borrow().id())?;
+        let precision = decoder.u8()?;
+
+        if !(MIN_PRECISION..=MAX_PRECISION).contains(&precision) {
+            return Err(DecodeError::Invalid("HyperLogLog precision out of range"));
+        }
+
+        let num_registers = 1u32 << precision;
+        let max_rank = 64 - precision + 1;
+        let registers = match decoder.u8()? {
+            0 => {
+                let count = decoder.u32()?;
+
+                if count as usize > (num_registers / 4) as usize {
+                    return Err(DecodeError::Invalid("too many sparse HyperLogLog entries"));
+                }
+
+                let entries = (0..count)
+                    .map(|_| decoder.u32())
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                let valid_entry = |entry: &u32| {
+                    let rank = (entry & RANK_MASK) as u8;
+                    (entry >> RANK_BITS) < num_registers && rank > 0 && rank <= max_rank
+                };
+                if !entries.iter().all(valid_entry)
+                    || !entries
+                        .windows(2)
+                        .all(|w| (w[0] >> RANK_BITS) < (w[1] >> RANK_BITS))
+                {
+                    return Err(DecodeError::Invalid("invalid sparse HyperLogLog entries"));
+                }
+
+                Registers::Sparse(entries)
+            }
+            1 => {
+                let registers = decoder.bytes(num_registers as usize)?.to_vec();
+
+                if registers.iter().any(|rank| *rank > max_rank) {
+                    return Err(DecodeError::Invalid("HyperLogLog rank out of range"));
+                }
+
+                Registers::Dense(registers)
+            }
+            _ => return Err(DecodeError::Invalid("unknown HyperLogLog representation")),
+        };
+
+        decoder.finish()?;
+        Ok(HyperLogLog {
+            params,
+            precision,
+            registers,
+        })
+    }
+}
+
+/// See the [crate docs](crate#data-structures) for the
+/// [`Debug`](std::fmt::Debug) representation of data structures.
+impl<P: Borrow<Params>> std::fmt::Debug for HyperLogLog<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HyperLogLog")
+            .field("params", &self.params().id())
+            .field("precision", &self.precision)
+            .field("sparse", &self.is_sparse())
+            .field("estimate", &self.estimate())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hll::HyperLogLog;
+    use crate::{DecodeError, MergeError, Params};
+
+    fn relative_error(estimate: f64, exact: usize) -> f64 {
+        (estimate - exact as f64).abs() / exact as f64
+    }
+
+    #[test]
+    fn test_accuracy() {
+        let params = Params::derive(0, b"hll");
+
+        for precision in [10u8, 14].iter().copied() {
+            let mut hll = HyperLogLog::new(&params, precision);
+            // 5 standard errors.
+            let tolerance = 5.0 * 1.04 / ((1u64 << precision) as f64).sqrt();
+            let mut exact = 0;
+
+            assert_eq!(hll.estimate(), 0.0);
+            for count in [10usize, 100, 1000, 10_000, 100_000].iter().copied() {
+                while exact < count {
+                    hll.insert(&(exact as u64));
+                    // `exact / 2` was already inserted: a duplicate.
+                    hll.insert(&(exact as u64 / 2));
+                    exact += 1;
+                }
+
+                // Re-inserting every item changes neither the estimate
+                // nor the serialized sketch.
+                let estimate = hll.estimate();
+                let bytes = hll.to_bytes();
+                for item in 0..exact {
+                    hll.insert(&(item as u64));
+                }
+                assert_eq!(hll.estimate(), estimate);
+                assert_eq!(hll.to_bytes(), bytes);
+
+                let error = relative_error(hll.estimate(), exact);
+                assert!(
+                    error < tolerance,
+                    "precision {} count {} estimate {}",
+                    precision,
+                    exact,
+                    hll.estimate()
+                );
+            }
+
+            assert!(!hll.is_sparse());
+        }
+    }
+
+    #[test]
+    fn test_sparse() {
+        let params = Params::derive(0, b"hll");
+        let mut hll = HyperLogLog::new(&params, 14);
+
+        for i in 0..1000u64 {
+            hll.insert(&i);
+        }
+
+        assert!(hll.is_sparse());
+        assert!(relative_error(hll.estimate(), 1000) < 0.05);
+
+        hll.clear();
+        assert!(hll.is_sparse());
+        assert_eq!(hll.estimate(), 0.0);
+    }
+
+    #[test]
+    fn test_merge() {
+        let params = Params::derive(0, b"hll");
+        let mut all = HyperLogLog::new(&params, 12);
+        let mut left = HyperLogLog::new(&params, 12);
+        let mut small = HyperLogLog::new(&params, 12);
+
+        for i in 0..50_000u64 {
+            all.insert(&i);
+            left.insert(&i);
+        }
+
+        for i in 40_000..60_000u64 {
+            all.insert(&i);
+            let mut right = HyperLogLog::new(&params, 12);
+            right.insert(&i);
+            left.merge(&right).expect("compatible estimators");
+        }
+
+        for i in 0..100u64 {
+            small.insert(&i);
+        }
+
+        assert!(small.is_sparse());
+        assert_eq!(left.estimate(), all.estimate());
+        assert!(relative_error(left.estimate(), 60_000) < 0.1);
+
+        // Merging a dense estimator into a sparse one.
+        small.merge(&all).expect("compatible estimators");
+        assert!(!small.is_sparse());
+        assert_eq!(small.estimate(), all.estimate());
+
+        let other = Params::derive(1, b"hll");
+        assert_eq!(
+            left.merge(&HyperLogLog::new(&other, 12)),
+            Err(MergeError::ParamsMismatch {
+                left: params.id(),
+                right: other.id()
+            })
+        );
+        assert_eq!(
+            left.merge(&HyperLogLog::new(&params, 13)),
+            Err(MergeError::ShapeMismatch)
+        );
+    }
+
+    #[test]
+    fn test_serialization() {
+        let params = Params::derive(0, b"hll");
+        let mut hll = HyperLogLog::new(&params, 8);
+
+        for count in [20u64, 2000].iter().copied() {
+            for i in 0..count {
+                hll.insert(&i);
+            }
+
+            let bytes = hll.to_bytes();
+            let copy = HyperLogLog::from_bytes(&params, &bytes).expect("valid encoding");
+
+            assert_eq!(copy.is_sparse(), hll.is_sparse());
+            assert_eq!(copy.estimate(), hll.estimate());
+            assert_eq!(
+                HyperLogLog::from_bytes(&params, &bytes[..bytes.len() - 1]).err(),
+                Some(DecodeError::Truncated)
+            );
+
+            let other = Params::derive(1, b"hll");
+            assert_eq!(
+                HyperLogLog::from_bytes(&other, &bytes).err(),
+                Some(DecodeError::ParamsMismatch {
+                    expected: other.id(),
+                    found: params.id()
+                })
+            );
+        }
+    }
+}
@@ -8,10 +8,62 @@
 //!
 //! See the [reference repo](https://github.com/backtrace-labs/umash)
 //! for more details and proofs.
+//!
+//! # Data structures
+//!
+//! The data structures built on UMASH (e.g., [`bloom::BloomFilter`],
+//! [`cms::CountMinSketch`], or [`hll::HyperLogLog`]) share a few
+//! conventions:
+//!
+//! - Each structure is parameterised on how it holds on to its
+//!   [`Params`]: `P: Borrow<Params>` may be a `&Params`, an owned
+//!   [`Params`], an [`Arc<Params>`](std::sync::Arc), etc.
+//! - As for [`Params::hash`] and [`Params::fingerprint`], items go
+//!   through [`std::hash::Hash`], which is not guaranteed to be stable
+//!   across platforms.  When a structure is serialised, merged, or
+//!   queried by another program, feed it hash values or
+//!   [`Fingerprint`]s computed from raw bytes instead; each module
+//!   documents the methods that accept them.
+//! - Their [`Debug`](std::fmt::Debug) representation summarises the
+//!   structure's shape (and occupancy, when that's cheap to compute),
+//!   rather than listing every slot.
 
-use std::marker::PhantomData;
 use umash_sys as ffi;
 
+pub mod bloom;
+pub mod cas;
+pub mod chunking;
+pub mod cms;
+pub mod cuckoo;
+pub mod dir;
+pub mod dupes;
+pub mod hll;
+pub mod merkle;
+pub mod minhash;
+pub mod range;
+pub mod shard;
+pub mod simhash;
+pub mod test_vectors;
+pub mod xor_filter;
+
+mod codec;
+#[cfg(feature = "digest")]
+mod digest_impl;
+mod domain;
+mod fingerprint_map;
+mod multi_hasher;
+mod params_set;
+#[cfg(test)]
+mod test_util;
+
+pub use codec::{DecodeError, MergeError};
+pub use domain::Domain;
+pub use fingerprint_map::{
+    FingerprintBuildHasher, FingerprintHasher, FingerprintMap, FingerprintSet,
+};
+pub use multi_hasher::MultiHasher;
+pub use params_set::ParamsSet;
+
 /// A [`Params`] stores a set of hashing parameters that define a
 /// specific UMASH function.
 ///
@@ -104,6 +156,68 @@ impl Fingerprint {
     }
 }
 
+/// [`Fingerprint`]s are displayed as 32 lowercase hex digits: the
+/// primary hash value, followed by the secondary.
+impl std::fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:016x}{:016x}", self.hash[0], self.hash[1])
+    }
+}
+
+/// A [`ParamsId`] is a short identifier for the parameter values in a
+/// [`Params`] struct, computed by [`Params::id`].
+///
+/// The identifier is a 64-bit hash of the parameter values, so two
+/// [`Params`] with the same identifier are almost certainly equal.
+/// Like [`Params::derive`], the identifier is stable across
+/// processes, programs, architectures, and versions of umash-rs.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct ParamsId(pub u64);
+
+/// [`ParamsId`]s are displayed as 16 lowercase hex digits.
+impl std::fmt::Display for ParamsId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+/// A [`TaggedFingerprint`] pairs a [`Fingerprint`] with the
+/// [`ParamsId`] of the [`Params`] that computed it, for storage
+/// alongside the fingerprinted data.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct TaggedFingerprint {
+    pub params_id: ParamsId,
+    pub fingerprint: Fingerprint,
+}
+
+impl TaggedFingerprint {
+    /// Returns a [`TaggedFingerprint`] for this `params_id` and
+    /// `fingerprint`.
+    #[inline(always)]
+    pub fn new(params_id: ParamsId, fingerprint: Fingerprint) -> Self {
+        TaggedFingerprint {
+            params_id,
+            fingerprint,
+        }
+    }
+}
+
+/// A [`Verification`] is the result of checking bytes against an
+/// expected [`Fingerprint`] with [`Params::verify`] or
+/// [`Params::verify_primary`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Verification {
+    /// Whether the [`UmashComponent::Hash`] value matched
+    /// `Fingerprint::hash[0]`.
+    pub primary: bool,
+
+    /// Whether the full 128-bit [`Fingerprint`] was computed and
+    /// matched.  This implies that the primary hash matched as well.
+    /// Always false for [`Params::verify_primary`], which does not
+    /// compute the secondary hash.
+    pub full: bool,
+}
+
 /// A [`Hasher`] implements one of the two hash 64-bit functions
 /// defined by a specific [`Params`] struct, further tweaked by a
 /// seed.  Construct [`Hasher`]s with [`Params::hasher`],
@@ -117,8 +231,10 @@ impl Fingerprint {
 /// In other words, it doesn't matter how we partition an input, the
 /// [`Hasher`] computes the same hash value as a one-shot UMASH call
 /// for the parameters and the concatenated input bytes.
-#[derive(Clone)]
-pub struct Hasher<'params>(ffi::umash_state, PhantomData<&'params Params>);
+pub struct Hasher<'params> {
+    state: ffi::umash_state,
+    params: &'params Params,
+}
 
 /// A [`Fingerprinter`] implements the 128-bit fingerprinting function
 /// defined by a specific [`Params`] struct, further tweaked by a seed.
@@ -144,8 +260,10 @@ pub struct Hasher<'params>(ffi::umash_state, PhantomData<&'params Params>);
 /// makes sense to pass a [`Fingerprinter`] to a [`std::hash::Hash`],
 /// and extract a [`Fingerprint`] for the input data with
 /// [`Fingerprinter::digest`].
-#[derive(Clone)]
-pub struct Fingerprinter<'params>(ffi::umash_fp_state, PhantomData<&'params Params>);
+pub struct Fingerprinter<'params> {
+    state: ffi::umash_fp_state,
+    params: &'params Params,
+}
 
 impl Params {
     /// Returns a new pseudo-unique [`Params`] value.
@@ -158,18 +276,34 @@ impl Params {
         // and the counter (with chacha20).  The parameters should be
         // unique and independently distributed, until we generate
         // 2^64 `Params` on the same thread.
-        thread_local!(static RANDOM_STATE: ([u8; 32], Cell<u64>) = {
-            let mut slice = [0u8; 32];
+        struct RandomState {
+            key: [u8; 32],
+            counter: Cell<u64>,
+        }
+
+        // Wipe the random bytes when the thread exits.
+        #[cfg(feature = "zeroize")]
+        impl Drop for RandomState {
+            fn drop(&mut self) {
+                zeroize::Zeroize::zeroize(&mut self.key);
+            }
+        }
 
-            getrandom::getrandom(&mut slice).expect("failed to generate 32 random bytes");
-            (slice, Cell::new(0))
+        thread_local!(static RANDOM_STATE: RandomState = {
+            let mut key = [0u8; 32];
+
+            getrandom::getrandom(&mut key).expect("failed to generate 32 random bytes");
+            RandomState {
+                key,
+                counter: Cell::new(0),
+            }
         });
 
         RANDOM_STATE.with(|state| {
-            let counter = state.1.get();
-            state.1.set(counter.wrapping_add(1));
+            let counter = state.counter.get();
+            state.counter.set(counter.wrapping_add(1));
 
-            Params::derive(counter, &state.0)
+            Params::derive(counter, &state.key)
         })
     }
 
@@ -185,20 +319,62 @@ impl Params {
         let mut key_vec = [0u8; 32];
         let to_copy = key.len().min(key_vec.len());
 
-        key_vec[..to_copy].copy_from_slice(key);
+        key_vec[..to_copy].copy_from_slice(&key[..to_copy]);
         unsafe {
             ffi::umash_params_derive(&mut params.0, bits, key_vec.as_ptr() as *const _);
         }
 
+        #[cfg(feature = "zeroize")]
+        zeroize::Zeroize::zeroize(&mut key_vec);
+
         params
     }
 
+    /// Fills `key` with a 32-byte key for [`Params::derive`], derived
+    /// from these [`Params`] and `label`: the fingerprints of `label`
+    /// for seeds 0 and 1, in little-endian order.
+    pub(crate) fn fill_key(&self, label: &[u8], key: &mut [u8; 32]) {
+        let mut fingerprinter = self.fingerprinter(0);
+
+        for (seed, dst) in key.chunks_mut(16).enumerate() {
+            let fprint = fingerprinter
+                .reset_with_seed(seed as u64)
+                .write(label)
+                .digest();
+
+            dst[..8].copy_from_slice(&fprint.hash[0].to_le_bytes());
+            dst[8..].copy_from_slice(&fprint.hash[1].to_le_bytes());
+        }
+    }
+
+    /// Returns a stable [`ParamsId`] for the parameter values in this
+    /// [`Params`] struct.
+    ///
+    /// The identifier is a fixed UMASH function of the parameters, so
+    /// it is not meant to be kept secret, but should not be published
+    /// when the [`Params`] were derived from a secret key either.
+    pub fn id(&self) -> ParamsId {
+        // Hash the little-endian parameter words with a fixed set of
+        // parameters, to get the same identifier on all platforms.
+        thread_local!(static ID_PARAMS: Params = Params::derive(0, b"umash-rs ParamsId"));
+
+        ID_PARAMS.with(|id_params| {
+            let mut hasher = id_params.hasher(0);
+
+            for word in self.0.poly.iter().flatten().chain(self.0.oh.iter()) {
+                hasher.write(&word.to_le_bytes());
+            }
+
+            ParamsId(hasher.digest())
+        })
+    }
+
     /// Returns a [`Hasher`] for the primary UMASH function.
     ///
     /// The `seed` tweaks the hash value without any proven impact on
     /// collision rates for different seed values.
     #[inline(always)]
-    pub fn hasher(&self, seed: u64) -> Hasher {
+    pub fn hasher(&self, seed: u64) -> Hasher<'_> {
         self.component_hasher(seed, UmashComponent::Hash)
     }
 
@@ -207,7 +383,7 @@ impl Params {
     /// The `seed` tweaks the hash value without any proven impact on
     /// collision rates for different seed values.
     #[inline(always)]
-    pub fn secondary_hasher(&self, seed: u64) -> Hasher {
+    pub fn secondary_hasher(&self, seed: u64) -> Hasher<'_> {
         self.component_hasher(seed, UmashComponent::Secondary)
     }
 
@@ -217,7 +393,7 @@ impl Params {
     /// The `seed` tweaks the hash value without any proven impact on
     /// collision rates for different seed values.
     #[inline(always)]
-    pub fn component_hasher(&self, seed: u64, which: UmashComponent) -> Hasher {
+    pub fn component_hasher(&self, seed: u64, which: UmashComponent) -> Hasher<'_> {
         Hasher::with_params(self, seed, which)
     }
 
@@ -226,7 +402,7 @@ impl Params {
     /// The `seed` tweaks the hash value without any proven impact on
     /// collision rates for different seed values.
     #[inline(always)]
-    pub fn fingerprinter(&self, seed: u64) -> Fingerprinter {
+    pub fn fingerprinter(&self, seed: u64) -> Fingerprinter<'_> {
         Fingerprinter::with_params(self, seed)
     }
 
@@ -271,6 +447,124 @@ impl Params {
         object.hash(&mut hasher);
         hasher.digest()
     }
+
+    /// Maps the [`Params::hash`] value for `object` to `[0, n)`; see
+    /// [`range::hash_to_range`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is zero.
+    pub fn hash_to_range(&self, object: impl std::hash::Hash, n: u64) -> u64 {
+        range::hash_to_range(self.hash(object), n)
+    }
+
+    /// Maps the [`Params::hash`] value for `object` to an `f64` in
+    /// `[0, 1)`; see [`range::hash_to_unit_f64`].
+    pub fn hash_to_unit_f64(&self, object: impl std::hash::Hash) -> f64 {
+        range::hash_to_unit_f64(self.hash(object))
+    }
+
+    /// Determines whether to sample `object`, when sampling a fraction
+    /// `rate` of all objects; see [`range::sample`].
+    pub fn sample(&self, object: impl std::hash::Hash, rate: f64) -> bool {
+        range::sample(self.hash(object), rate)
+    }
+
+    /// Determines whether the primary [`UmashComponent::Hash`] value
+    /// for `bytes` and `seed` matches `expected.hash[0]`.
+    ///
+    /// This is the primary-only check: it never computes the secondary
+    /// hash.  That's faster than computing the full [`Fingerprint`],
+    /// but the collision probability is that of the 64-bit hash: a
+    /// false return value is definitive, a true one is less
+    /// trustworthy than a full match with [`Params::verify`].
+    pub fn quick_check(&self, seed: u64, expected: &Fingerprint, bytes: &[u8]) -> bool {
+        let hash = unsafe {
+            ffi::umash_full(
+                &self.0,
+                seed,
+                UmashComponent::Hash as i32,
+                bytes.as_ptr() as *const _,
+                bytes.len() as u64,
+            )
+        };
+
+        hash == expected.hash()
+    }
+
+    /// Computes the [`Fingerprint`] for `bytes` and `seed`, and compares
+    /// it with `expected`, both for the primary hash value only, and for
+    /// the full 128-bit fingerprint.
+    ///
+    /// This always computes and checks the full fingerprint.  Call
+    /// [`Params::verify_primary`] or [`Params::quick_check`] instead to
+    /// only compute and check the primary hash, e.g., before deciding
+    /// whether the full check is worth it.
+    pub fn verify(&self, seed: u64, expected: &Fingerprint, bytes: &[u8]) -> Verification {
+        let fprint = unsafe {
+            ffi::umash_fprint(
+                &self.0,
+                seed,
+                bytes.as_ptr() as *const _,
+                bytes.len() as u64,
+            )
+        };
+
+        Verification {
+            primary: fprint.hash[0] == expected.hash(),
+            full: fprint.hash == expected.hash,
+        }
+    }
+
+    /// Checks `bytes` and `seed` against `expected` like
+    /// [`Params::verify`], but only computes and compares the primary
+    /// hash value, with [`Params::quick_check`].
+    ///
+    /// The full fingerprint is not checked, so the result's
+    /// [`Verification::full`] is always false.
+    pub fn verify_primary(&self, seed: u64, expected: &Fingerprint, bytes: &[u8]) -> Verification {
+        Verification {
+            primary: self.quick_check(seed, expected, bytes),
+            full: false,
+        }
+    }
+
+    /// Computes the primary [`UmashComponent::Hash`] value for the
+    /// fixed-size array `bytes` and `seed`, e.g., for UUIDs or 32-byte
+    /// content hashes.
+    ///
+    /// The result is identical to writing `bytes` to
+    /// [`Params::hasher`], without the length prefix that
+    /// [`Params::hash`] would add for an array, and without setting up
+    /// a streaming [`Hasher`].  This is only a convenience: `umash-sys`
+    /// does not expose UMASH's short, medium, and long input routines,
+    /// so the input path is still picked at runtime, like for any
+    /// other slice.
+    #[inline(always)]
+    pub fn hash_array<const N: usize>(&self, seed: u64, bytes: &[u8; N]) -> u64 {
+        unsafe {
+            ffi::umash_full(
+                &self.0,
+                seed,
+                UmashComponent::Hash as i32,
+                bytes.as_ptr() as *const _,
+                N as u64,
+            )
+        }
+    }
+
+    /// Computes the [`Fingerprint`] for the fixed-size array `bytes`
+    /// and `seed`.
+    ///
+    /// The result is identical to writing `bytes` to
+    /// [`Params::fingerprinter`]; see [`Params::hash_array`].
+    #[inline(always)]
+    pub fn fingerprint_array<const N: usize>(&self, seed: u64, bytes: &[u8; N]) -> Fingerprint {
+        let fprint =
+            unsafe { ffi::umash_fprint(&self.0, seed, bytes.as_ptr() as *const _, N as u64) };
+
+        Fingerprint { hash: fprint.hash }
+    }
 }
 
 /// The default constructor for [`Params`] returns a fresh unique set
@@ -282,6 +576,63 @@ impl Default for Params {
     }
 }
 
+/// [`Params`] are equal when they define the same UMASH functions,
+/// i.e., when all their parameter values are equal.
+impl PartialEq for Params {
+    fn eq(&self, other: &Params) -> bool {
+        self.0.poly == other.0.poly && self.0.oh == other.0.oh
+    }
+}
+
+impl Eq for Params {}
+
+/// Overwrites `state` with zeros, as bytes.  `zeroize::zeroize_flat_type`
+/// does the same, but needs zeroize 1.7 and Rust 1.60.
+///
+/// # Safety
+///
+/// `T` must be plain old data (e.g., an FFI state struct of integers)
+/// for which all-zero bytes are a valid value.
+#[cfg(feature = "zeroize")]
+pub(crate) unsafe fn zeroize_state<T: Copy>(state: &mut T) {
+    let bytes =
+        std::slice::from_raw_parts_mut(state as *mut T as *mut u8, std::mem::size_of::<T>());
+
+    zeroize::Zeroize::zeroize(bytes);
+}
+
+/// With the `zeroize` feature, [`Params`] can be explicitly wiped, and
+/// are always overwritten with zeros when dropped.  The all-zero
+/// [`Params`] are valid, but define a useless UMASH function.
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for Params {
+    fn zeroize(&mut self) {
+        self.0.poly.zeroize();
+        self.0.oh.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl zeroize::ZeroizeOnDrop for Params {}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Params {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(self);
+    }
+}
+
+/// The [`Debug`](std::fmt::Debug) representation of [`Params`] only
+/// shows their [`ParamsId`]: the parameter values themselves are
+/// effectively a secret key for UMASH's collision bounds.
+impl std::fmt::Debug for Params {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Params")
+            .field("id", &self.id())
+            .finish_non_exhaustive()
+    }
+}
+
 /// A *reference* to a [`Params`] struct may be passed to hashed
 /// collections.  The collection will use hashers derived from
 /// that static set of parameters (with `seed = 0`).
@@ -309,13 +660,16 @@ impl<'params> Hasher<'params> {
     /// values, albeit without any statistical bound on collisions.
     #[inline(always)]
     fn with_params(params: &'params Params, seed: u64, which: UmashComponent) -> Self {
-        let mut state = Hasher(unsafe { std::mem::zeroed() }, PhantomData);
+        let mut hasher = Hasher {
+            state: unsafe { std::mem::zeroed() },
+            params,
+        };
 
         unsafe {
-            ffi::umash_init(&mut state.0, &params.0, seed, which as i32);
+            ffi::umash_init(&mut hasher.state, &params.0, seed, which as i32);
         }
 
-        state
+        hasher
     }
 
     /// Updates the hash state by conceptually concatenating `bytes`
@@ -324,7 +678,7 @@ impl<'params> Hasher<'params> {
     pub fn write(&mut self, bytes: &[u8]) -> &mut Self {
         unsafe {
             ffi::umash_sink_update(
-                &mut self.0.sink,
+                &mut self.state.sink,
                 bytes.as_ptr() as *const _,
                 bytes.len() as u64,
             );
@@ -337,7 +691,110 @@ impl<'params> Hasher<'params> {
     /// and the bytes passed to [`Hasher::write`] so far.
     #[inline(always)]
     pub fn digest(&self) -> u64 {
-        unsafe { ffi::umash_digest(&self.0) }
+        unsafe { ffi::umash_digest(&self.state) }
+    }
+
+    /// Forgets all the bytes written so far, and brings the [`Hasher`]
+    /// back to the state it was in when constructed, with the same
+    /// [`Params`], [`UmashComponent`], and seed.
+    ///
+    /// This is cheaper than constructing a fresh [`Hasher`] for each
+    /// message.
+    #[inline(always)]
+    pub fn reset(&mut self) -> &mut Self {
+        self.reset_with_seed(self.state.sink.seed)
+    }
+
+    /// Forgets all the bytes written so far, and brings the [`Hasher`]
+    /// back to a fresh state for the same [`Params`] and
+    /// [`UmashComponent`], but with a new `seed`.
+    #[inline(always)]
+    pub fn reset_with_seed(&mut self, seed: u64) -> &mut Self {
+        let which = self.state.sink.hash_wanted as i32;
+
+        self.reinit(seed, which)
+    }
+
+    /// Re-initialises the hashing state for `seed` and the `which`
+    /// [`UmashComponent`] (as an `i32`).
+    #[inline(always)]
+    fn reinit(&mut self, seed: u64, which: i32) -> &mut Self {
+        // `umash_init` overwrites the whole state.
+        unsafe {
+            ffi::umash_init(&mut self.state, &self.params.0, seed, which);
+        }
+
+        self
+    }
+}
+
+/// Cloning a [`Hasher`] snapshots its state, including the bytes
+/// written so far.  [`Clone::clone_from`] overwrites the destination
+/// in place: cloning from a template [`Hasher`] that has already
+/// consumed a common prefix is a cheap way to hash many messages that
+/// share that prefix.
+impl Clone for Hasher<'_> {
+    #[inline(always)]
+    fn clone(&self) -> Self {
+        Hasher {
+            state: self.state,
+            params: self.params,
+        }
+    }
+
+    #[inline(always)]
+    fn clone_from(&mut self, source: &Self) {
+        self.state = source.state;
+        self.params = source.params;
+    }
+}
+
+/// The [`Debug`](std::fmt::Debug) representation of a [`Hasher`]
+/// shows the [`ParamsId`], the [`UmashComponent`], and the seed, but
+/// not the hashing state.
+impl std::fmt::Debug for Hasher<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let component = if self.state.sink.hash_wanted == 0 {
+            UmashComponent::Hash
+        } else {
+            UmashComponent::Secondary
+        };
+
+        f.debug_struct("Hasher")
+            .field("params", &self.params.id())
+            .field("component", &component)
+            .field("seed", &self.state.sink.seed)
+            .finish_non_exhaustive()
+    }
+}
+
+/// With the `zeroize` feature, a [`Hasher`]'s state (which includes
+/// a copy of some of its [`Params`]) is overwritten with zeros when
+/// dropped.  Explicitly zeroizing a [`Hasher`] wipes the bytes written
+/// so far, and leaves the [`Hasher`] in its initial state.
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for Hasher<'_> {
+    fn zeroize(&mut self) {
+        // Save the seed and component before wiping them.
+        let seed = self.state.sink.seed;
+        let which = self.state.sink.hash_wanted as i32;
+
+        // Safety: `umash_state` is plain old data, and is valid when
+        // all zero... and we immediately re-initialise it anyway.
+        unsafe { zeroize_state(&mut self.state) };
+        self.reinit(seed, which);
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl zeroize::ZeroizeOnDrop for Hasher<'_> {}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Hasher<'_> {
+    fn drop(&mut self) {
+        // Safety: `umash_state` is plain old data, and is valid when
+        // all zero.
+        unsafe { zeroize_state(&mut self.state) };
     }
 }
 
@@ -400,13 +857,16 @@ impl<'params> Fingerprinter<'params> {
     /// collisions.
     #[inline(always)]
     fn with_params(params: &'params Params, seed: u64) -> Self {
-        let mut state = Self(unsafe { std::mem::zeroed() }, PhantomData);
+        let mut fingerprinter = Fingerprinter {
+            state: unsafe { std::mem::zeroed() },
+            params,
+        };
 
         unsafe {
-            ffi::umash_fp_init(&mut state.0, &params.0, seed);
+            ffi::umash_fp_init(&mut fingerprinter.state, &params.0, seed);
         }
 
-        state
+        fingerprinter
     }
 
     /// Updates the fingerprinting state by conceptually concatenating
@@ -415,7 +875,7 @@ impl<'params> Fingerprinter<'params> {
     pub fn write(&mut self, bytes: &[u8]) -> &mut Self {
         unsafe {
             ffi::umash_sink_update(
-                &mut self.0.sink,
+                &mut self.state.sink,
                 bytes.as_ptr() as *const _,
                 bytes.len() as u64,
             );
@@ -429,10 +889,96 @@ impl<'params> Fingerprinter<'params> {
     /// [`Fingerprinter::write`] so far.
     #[inline(always)]
     pub fn digest(&self) -> Fingerprint {
-        let fprint = unsafe { ffi::umash_fp_digest(&self.0) };
+        let fprint = unsafe { ffi::umash_fp_digest(&self.state) };
 
         Fingerprint { hash: fprint.hash }
     }
+
+    /// Forgets all the bytes written so far, and brings the
+    /// [`Fingerprinter`] back to the state it was in when constructed,
+    /// with the same [`Params`] and seed.
+    ///
+    /// This is cheaper than constructing a fresh [`Fingerprinter`] for
+    /// each message.
+    #[inline(always)]
+    pub fn reset(&mut self) -> &mut Self {
+        self.reset_with_seed(self.state.sink.seed)
+    }
+
+    /// Forgets all the bytes written so far, and brings the
+    /// [`Fingerprinter`] back to a fresh state for the same [`Params`],
+    /// but with a new `seed`.
+    #[inline(always)]
+    pub fn reset_with_seed(&mut self, seed: u64) -> &mut Self {
+        // `umash_fp_init` overwrites the whole state.
+        unsafe {
+            ffi::umash_fp_init(&mut self.state, &self.params.0, seed);
+        }
+
+        self
+    }
+}
+
+/// Cloning a [`Fingerprinter`] snapshots its state, including the
+/// bytes written so far.  [`Clone::clone_from`] overwrites the
+/// destination in place: cloning from a template [`Fingerprinter`]
+/// that has already consumed a common prefix is a cheap way to
+/// fingerprint many messages that share that prefix.
+impl Clone for Fingerprinter<'_> {
+    #[inline(always)]
+    fn clone(&self) -> Self {
+        Fingerprinter {
+            state: self.state,
+            params: self.params,
+        }
+    }
+
+    #[inline(always)]
+    fn clone_from(&mut self, source: &Self) {
+        self.state = source.state;
+        self.params = source.params;
+    }
+}
+
+/// The [`Debug`](std::fmt::Debug) representation of a
+/// [`Fingerprinter`] shows the [`ParamsId`] and the seed, but not the
+/// fingerprinting state.
+impl std::fmt::Debug for Fingerprinter<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Fingerprinter")
+            .field("params", &self.params.id())
+            .field("seed", &self.state.sink.seed)
+            .finish_non_exhaustive()
+    }
+}
+
+/// With the `zeroize` feature, a [`Fingerprinter`]'s state (which
+/// includes a copy of some of its [`Params`]) is overwritten with zeros
+/// when dropped.  Explicitly zeroizing a [`Fingerprinter`] wipes the
+/// bytes written so far, and leaves the [`Fingerprinter`] in its
+/// initial state.
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for Fingerprinter<'_> {
+    fn zeroize(&mut self) {
+        let seed = self.state.sink.seed;
+
+        // Safety: `umash_fp_state` is plain old data, and is valid
+        // when all zero... and we immediately re-initialise it anyway.
+        unsafe { zeroize_state(&mut self.state) };
+        self.reset_with_seed(seed);
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl zeroize::ZeroizeOnDrop for Fingerprinter<'_> {}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Fingerprinter<'_> {
+    fn drop(&mut self) {
+        // Safety: `umash_fp_state` is plain old data, and is valid
+        // when all zero.
+        unsafe { zeroize_state(&mut self.state) };
+    }
 }
 
 /// Converts a `&Params` to [`Fingerprinter`] by constructing a fresh
@@ -500,7 +1046,7 @@ impl std::io::Write for Fingerprinter<'_> {
 
 #[cfg(test)]
 mod tests {
-    use crate::{Fingerprint, Fingerprinter, Params, UmashComponent};
+    use crate::{Fingerprint, Fingerprinter, Params, ParamsId, UmashComponent};
 
     #[test]
     fn test_example_case() {
@@ -522,6 +1068,7 @@ mod tests {
             fprint.component(UmashComponent::Secondary),
             0x3a52693519575aba
         );
+        assert_eq!(fprint.to_string(), "398c5bb5cc113d033a52693519575aba");
     }
 
     #[test]
@@ -633,6 +1180,280 @@ mod tests {
         assert_eq!(fingerprint, Fingerprint::new(hash, secondary));
     }
 
+    #[test]
+    fn test_params_id() {
+        let params = Params::derive(0, b"hello example.c");
+
+        assert_eq!(params.id(), ParamsId(0x103719f22bec0a02));
+        assert_eq!(params.id().to_string(), "103719f22bec0a02");
+        assert_eq!(params.id(), params.clone().id());
+        assert_ne!(params.id(), Params::derive(1, b"hello example.c").id());
+        assert_ne!(params.id(), Params::derive(0, b"backtrace").id());
+    }
+
+    #[test]
+    fn test_params_eq() {
+        let params = Params::derive(0, b"hello example.c");
+
+        assert!(params == params.clone());
+        assert!(params == Params::derive(0, b"hello example.c"));
+        assert!(params != Params::derive(1, b"hello example.c"));
+        assert!(Params::new() != Params::new());
+    }
+
+    #[test]
+    fn test_debug() {
+        let params = Params::derive(0, b"hello example.c");
+        let mut hasher = params.secondary_hasher(42);
+        let mut fprint = params.fingerprinter(43);
+
+        hasher.write(b"the quick brown fox");
+        fprint.write(b"the quick").write(b" brown fox");
+
+        assert_eq!(
+            format!("{:?}", params),
+            "Params { id: ParamsId(1168431156235602434), .. }"
+        );
+        assert_eq!(
+            format!("{:?}", hasher),
+            "Hasher { params: ParamsId(1168431156235602434), component: Secondary, seed: 42, .. }"
+        );
+        assert_eq!(
+            format!("{:?}", fprint),
+            "Fingerprinter { params: ParamsId(1168431156235602434), seed: 43, .. }"
+        );
+    }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn test_zeroize() {
+        use zeroize::Zeroize;
+
+        let mut params = Params::derive(0, b"hello example.c");
+        let mut hasher = params.hasher(42);
+        let mut fprint = params.fingerprinter(42);
+
+        hasher.write(b"garbage");
+        hasher.zeroize();
+        hasher.write(b"the quick brown fox");
+        assert_eq!(hasher.digest(), 0x398c5bb5cc113d03);
+
+        fprint.write(b"garbage");
+        fprint.zeroize();
+        fprint.write(b"the quick brown fox");
+        assert_eq!(
+            fprint.digest(),
+            Fingerprint::new(0x398c5bb5cc113d03, 0x3a52693519575aba)
+        );
+
+        // Zeroizing a secondary hasher keeps it a secondary hasher.
+        let mut secondary = params.secondary_hasher(42);
+        secondary.write(b"garbage");
+        secondary.zeroize();
+        secondary.write(b"the quick brown fox");
+        assert_eq!(secondary.digest(), 0x3a52693519575aba);
+
+        drop(hasher);
+        drop(fprint);
+        drop(secondary);
+        params.zeroize();
+        assert!(params.0.poly.iter().flatten().all(|x| *x == 0));
+        assert!(params.0.oh.iter().all(|x| *x == 0));
+    }
+
+    #[test]
+    fn test_reset() {
+        let params = Params::derive(0, b"hello example.c");
+        let mut hasher = params.hasher(42);
+        let mut secondary = params.secondary_hasher(42);
+        let mut fprint = params.fingerprinter(42);
+
+        // Cover the short, medium, and long input code paths.
+        let messages: Vec<Vec<u8>> = [0usize, 7, 8, 9, 16, 17, 255, 256, 257, 5000]
+            .iter()
+            .map(|len| (0..*len).map(|i| i as u8).collect())
+            .collect();
+
+        for message in &messages {
+            for seed in [0u64, 42].iter().copied() {
+                assert_eq!(
+                    hasher.reset_with_seed(seed).write(message).digest(),
+                    params.hasher(seed).write(message).digest()
+                );
+                assert_eq!(
+                    secondary.reset_with_seed(seed).write(message).digest(),
+                    params.secondary_hasher(seed).write(message).digest()
+                );
+                assert_eq!(
+                    fprint.reset_with_seed(seed).write(message).digest(),
+                    params.fingerprinter(seed).write(message).digest()
+                );
+            }
+
+            hasher.write(b"garbage");
+            fprint.write(b"garbage");
+            assert_eq!(
+                hasher.reset().write(message).digest(),
+                params.hasher(42).write(message).digest()
+            );
+            assert_eq!(
+                fprint.reset().write(message).digest(),
+                params.fingerprinter(42).write(message).digest()
+            );
+        }
+
+        hasher.reset_with_seed(42).write(b"the quick brown fox");
+        assert_eq!(hasher.digest(), 0x398c5bb5cc113d03);
+        assert_eq!(
+            format!("{:?}", hasher.reset()),
+            format!("{:?}", params.hasher(42))
+        );
+    }
+
+    #[test]
+    fn test_clone_from() {
+        let params = Params::derive(0, b"hello example.c");
+        let mut template = params.fingerprinter(42);
+        let mut fprint = params.fingerprinter(0);
+
+        template.write(b"the quick ");
+        fprint.write(b"garbage");
+        fprint.clone_from(&template);
+        fprint.write(b"brown fox");
+        assert_eq!(
+            fprint.digest(),
+            Fingerprint::new(0x398c5bb5cc113d03, 0x3a52693519575aba)
+        );
+
+        let mut hasher = params.secondary_hasher(0);
+        hasher.clone_from(&params.hasher(42));
+        hasher.write(b"the quick brown fox");
+        assert_eq!(hasher.digest(), 0x398c5bb5cc113d03);
+        assert_eq!(hasher.clone().digest(), 0x398c5bb5cc113d03);
+    }
+
+    #[test]
+    fn test_quick_check() {
+        let params = Params::derive(0, b"hello example.c");
+        let expected = Fingerprint::new(0x398c5bb5cc113d03, 0x3a52693519575aba);
+
+        assert!(params.quick_check(42, &expected, b"the quick brown fox"));
+        assert!(!params.quick_check(42, &expected, b"the quick brown dog"));
+        assert!(!params.quick_check(0, &expected, b"the quick brown fox"));
+
+        // Only the primary hash is checked.
+        let wrong_secondary = Fingerprint::new(expected.hash(), 0);
+        assert!(params.quick_check(42, &wrong_secondary, b"the quick brown fox"));
+    }
+
+    #[test]
+    fn test_derive_long_key() {
+        let key = b"a key that is longer than the 32 bytes that UMASH derivation consumes";
+
+        assert_eq!(Params::derive(0, key), Params::derive(0, &key[..32]));
+        assert_ne!(Params::derive(0, key), Params::derive(0, &key[..31]));
+    }
+
+    #[test]
+    fn test_hash_array() {
+        let params = Params::derive(0, b"hello example.c");
+
+        assert_eq!(
+            params.hash_array(42, b"the quick brown fox"),
+            0x398c5bb5cc113d03
+        );
+        assert_eq!(
+            params.fingerprint_array(42, b"the quick brown fox"),
+            Fingerprint::new(0x398c5bb5cc113d03, 0x3a52693519575aba)
+        );
+
+        fn check<const N: usize>(params: &Params, seed: u64) {
+            let mut bytes = [0u8; N];
+
+            for (i, byte) in bytes.iter_mut().enumerate() {
+                *byte = (i * 31 + 7) as u8;
+            }
+
+            assert_eq!(
+                params.hash_array(seed, &bytes),
+                params.hasher(seed).write(&bytes).digest()
+            );
+            assert_eq!(
+                params.fingerprint_array(seed, &bytes),
+                params.fingerprinter(seed).write(&bytes).digest()
+            );
+        }
+
+        // Cover UMASH's short, medium, and long input paths.
+        check::<0>(&params, 1);
+        check::<8>(&params, 2);
+        check::<9>(&params, 3);
+        check::<16>(&params, 4);
+        check::<17>(&params, 5);
+        check::<32>(&params, 6);
+        check::<300>(&params, 8);
+    }
+
+    #[test]
+    fn test_verify() {
+        use crate::Verification;
+
+        let params = Params::derive(0, b"hello example.c");
+        let expected = Fingerprint::new(0x398c5bb5cc113d03, 0x3a52693519575aba);
+        let message = b"the quick brown fox";
+
+        assert_eq!(
+            params.verify(42, &expected, message),
+            Verification {
+                primary: true,
+                full: true
+            }
+        );
+        assert_eq!(
+            params.verify(42, &Fingerprint::new(expected.hash(), 0), message),
+            Verification {
+                primary: true,
+                full: false
+            }
+        );
+        assert_eq!(
+            params.verify(42, &expected, b"the quick brown dog"),
+            Verification {
+                primary: false,
+                full: false
+            }
+        );
+
+        // The primary-only check never reports a full match.
+        assert_eq!(
+            params.verify_primary(42, &expected, message),
+            Verification {
+                primary: true,
+                full: false
+            }
+        );
+        assert_eq!(
+            params.verify_primary(42, &Fingerprint::new(expected.hash(), 0), message),
+            Verification {
+                primary: true,
+                full: false
+            }
+        );
+        assert_eq!(
+            params.verify_primary(42, &expected, b"the quick brown dog"),
+            Verification {
+                primary: false,
+                full: false
+            }
+        );
+
+        // The one-shot and incremental paths agree on long inputs.
+        let long_input = vec![42u8; 10000];
+        let long_fprint = params.fingerprinter(1).write(&long_input).digest();
+        assert!(params.quick_check(1, &long_fprint, &long_input));
+        assert!(params.verify(1, &long_fprint, &long_input).full);
+    }
+
     #[test]
     fn test_hash_map() {
         use std::collections::HashMap;
@@ -0,0 +1,448 @@
+//! A Merkle tree of UMASH [`Fingerprint`]s over the fixed-size blocks
+//! of a byte stream, to recompute and verify fingerprints for large,
+//! partially modified inputs without rereading everything.
+//!
+//! Each leaf is the [`Fingerprint`] of one block, with `seed = 0`.
+//! Each internal node is the [`Fingerprint`], with `seed = 1`, of the
+//! concatenated little-endian bytes of its (one or two) children's
+//! fingerprints; the separate seeds keep leaves and internal nodes
+//! apart.  Updating one block only recomputes the fingerprints on the
+//! path from its leaf to the root, and a [`MerkleProof`] lets a
+//! reader check one block against the root fingerprint alone.
+//!
+//! # Accidents, not adversaries
+//!
+//! UMASH is not a cryptographic hash function, and neither is this
+//! tree: it detects accidental corruption (bit rot, torn writes,
+//! stale blocks) with the collision probability of the 128-bit
+//! [`Fingerprint`], as long as the corruption is independent of the
+//! [`Params`].  An attacker who knows the [`Params`] can craft blocks
+//! or proofs that verify against a given root; use a cryptographic
+//! hash when the data or the proofs may be tampered with.
+use crate::{Fingerprint, Params};
+use std::borrow::Borrow;
+use std::io::Read;
+
+const LEAF_SEED: u64 = 0;
+const NODE_SEED: u64 = 1;
+
+/// Returns the internal node for `children`.
+fn parent(params: &Params, children: &[Fingerprint]) -> Fingerprint {
+    let mut fingerprinter = params.fingerprinter(NODE_SEED);
+
+    for child in children {
+        fingerprinter
+            .write(&child.hash[0].to_le_bytes())
+            .write(&child.hash[1].to_le_bytes());
+    }
+
+    fingerprinter.digest()
+}
+
+/// Returns the fingerprints of the `block_size`-byte blocks read from
+/// `reader`, with at least one (empty) block.
+fn read_leaves<R: Read>(
+    params: &Params,
+    block_size: usize,
+    mut reader: R,
+) -> std::io::Result<Vec<Fingerprint>> {
+    let mut leaves = Vec::new();
+    let mut fingerprinter = params.fingerprinter(LEAF_SEED);
+    let mut block = vec![0u8; block_size];
+
+    loop {
+        // Fill a whole block, unless we hit EOF.
+        let mut filled = 0;
+        while filled < block_size {
+            match reader.read(&mut block[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        if filled > 0 || leaves.is_empty() {
+            leaves.push(fingerprinter.reset().write(&block[..filled]).digest());
+        }
+
+        if filled < block_size {
+            return Ok(leaves);
+        }
+    }
+}
+
+/// A [`MerkleTree`] stores the fingerprints of all the blocks of an
+/// input, and of all the internal nodes above them.
+///
+/// See the [crate docs](crate#data-structures) for the choice of
+/// `P`.
+#[derive(Clone)]
+pub struct MerkleTree<P: Borrow<Params>> {
+    params: P,
+    block_size: usize,
+    // `levels[0]` holds the leaves, and the last level the root.
+    levels: Vec<Vec<Fingerprint>>,
+}
+
+/// A [`MerkleProof`] lists the sibling fingerprints on the path from a
+/// leaf to the root of a [`MerkleTree`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MerkleProof {
+    /// The index of the leaf.
+    pub index: usize,
+    /// The sibling at each level, from the leaves up; `None` when the
+    /// node is the last one on its level, without a sibling.
+    pub siblings: Vec<Option<Fingerprint>>,
+}
+
+impl<P: Borrow<Params>> MerkleTree<P> {
+    /// Returns the [`MerkleTree`] for `data`, split in blocks of
+    /// `block_size` bytes (the last block may be shorter).  Empty
+    /// inputs have a single, empty, block.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `block_size` is zero.
+    pub fn new(params: P, block_size: usize, data: &[u8]) -> Self {
+        assert!(block_size > 0, "blocks must not be empty");
+
+        let leaves = read_leaves(params.borrow(), block_size, data)
+            .expect("reading from a slice never fails");
+
+        Self::from_leaves(params, block_size, leaves)
+    }
+
+    /// Returns the [`MerkleTree`] for the bytes read from `reader`,
+    /// split in blocks of `block_size` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `block_size` is zero.
+    pub fn from_reader<R: Read>(params: P, block_size: usize, reader: R) -> std::io::Result<Self> {
+        assert!(block_size > 0, "blocks must not be empty");
+
+        let leaves = read_leaves(params.borrow(), block_size, reader)?;
+
+        Ok(Self::from_leaves(params, block_size, leaves))
+    }
+
+    /// Returns the [`MerkleTree`] for the block fingerprints `leaves`,
+    /// computed with `params` and `seed = 0`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `block_size` is zero or `leaves` is empty.
+    pub fn from_leaves(params: P, block_size: usize, leaves: Vec<Fingerprint>) -> Self {
+        assert!(block_size > 0, "blocks must not be empty");
+        assert!(!leaves.is_empty(), "Merkle trees need at least one leaf");
+
+        let mut levels = vec![leaves];
+        while levels[levels.len() - 1].len() > 1 {
+            let next = levels[levels.len() - 1]
+                .chunks(2)
+                .map(|children| parent(params.borrow(), children))
+                .collect();
+
+            levels.push(next);
+        }
+
+        MerkleTree {
+            params,
+            block_size,
+            levels,
+        }
+    }
+
+    /// Returns the [`Params`] for this tree.
+    #[inline(always)]
+    pub fn params(&self) -> &Params {
+        self.params.borrow()
+    }
+
+    /// Returns the size of each block, in bytes.
+    #[inline(always)]
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// Returns the number of leaves (blocks).
+    #[inline(always)]
+    pub fn num_leaves(&self) -> usize {
+        self.levels[0].len()
+    }
+
+    /// Returns the fingerprint of block `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    #[inline(always)]
+    pub fn leaf(&self, index: usize) -> Fingerprint {
+        self.levels[0][index]
+    }
+
+    /// Returns the root fingerprint, which summarises the whole input.
+    #[inline(always)]
+    pub fn root(&self) -> Fingerprint {
+        self.levels[self.levels.len() - 1][0]
+    }
+
+    /// Replaces the contents of block `index` with `block`, and
+    /// recomputes the fingerprints from that leaf up to the root.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds, or if `block` is not
+    /// exactly [`MerkleTree::block_size`] bytes long.  Only the last
+    /// block may be shorter, and only empty when it's the only block,
+    /// as for [`MerkleTree::new`].
+    pub fn update_leaf(&mut self, index: usize, block: &[u8]) {
+        let last = self.num_leaves() - 1;
+
+        assert!(index <= last, "leaf index out of bounds");
+        assert!(
+            block.len() == self.block_size
+                || (index == last
+                    && block.len() < self.block_size
+                    && (last == 0 || !block.is_empty())),
+            "only the last block may be shorter than the block size"
+        );
+
+        let fprint = self.params().fingerprinter(LEAF_SEED).write(block).digest();
+
+        self.update_leaf_fingerprint(index, fprint)
+    }
+
+    /// Replaces the fingerprint of block `index` with `fprint`, and
+    /// recomputes the fingerprints from that leaf up to the root.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn update_leaf_fingerprint(&mut self, index: usize, fprint: Fingerprint) {
+        self.levels[0][index] = fprint;
+
+        let mut index = index;
+        for level in 1..self.levels.len() {
+            index /= 2;
+
+            let below = &self.levels[level - 1];
+            let children = &below[2 * index..(2 * index + 2).min(below.len())];
+            self.levels[level][index] = parent(self.params.borrow(), children);
+        }
+    }
+
+    /// Returns the indices of the leaves that differ between `self`
+    /// and `other`, which must have the same number of leaves.  Only
+    /// visits subtrees whose fingerprints differ.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the trees have different numbers of leaves.
+    pub fn changed_leaves<Q: Borrow<Params>>(&self, other: &MerkleTree<Q>) -> Vec<usize> {
+        assert_eq!(
+            self.num_leaves(),
+            other.num_leaves(),
+            "trees must have the same shape"
+        );
+
+        let mut ret = Vec::new();
+        let mut frontier = vec![0usize];
+        for level in (0..self.levels.len()).rev() {
+            let candidates: Vec<usize> = frontier
+                .into_iter()
+                .filter(|&index| self.levels[level][index] != other.levels[level][index])
+                .collect();
+
+            if level == 0 {
+                ret = candidates;
+                break;
+            }
+
+            let below = self.levels[level - 1].len();
+            frontier = candidates
+                .into_iter()
+                .flat_map(|index| 2 * index..(2 * index + 2).min(below))
+                .collect();
+        }
+
+        ret
+    }
+
+    /// Returns the [`MerkleProof`] for block `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn proof(&self, index: usize) -> MerkleProof {
+        assert!(index < self.num_leaves(), "leaf index out of bounds");
+
+        let mut siblings = Vec::with_capacity(self.levels.len() - 1);
+        let mut current = index;
+        for level in &self.levels[..self.levels.len() - 1] {
+            siblings.push(level.get(current ^ 1).copied());
+            current /= 2;
+        }
+
+        MerkleProof { index, siblings }
+    }
+}
+
+impl MerkleProof {
+    /// Determines whether `block` is the block at `self.index` of an
+    /// input whose [`MerkleTree`] has root fingerprint `root`.
+    pub fn verify(&self, params: &Params, root: &Fingerprint, block: &[u8]) -> bool {
+        let leaf = params.fingerprinter(LEAF_SEED).write(block).digest();
+
+        self.verify_leaf(params, root, &leaf)
+    }
+
+    /// Determines whether `leaf` is the fingerprint of the block at
+    /// `self.index` for a [`MerkleTree`] with root fingerprint `root`.
+    pub fn verify_leaf(&self, params: &Params, root: &Fingerprint, leaf: &Fingerprint) -> bool {
+        let mut current = *leaf;
+        let mut index = self.index;
+
+        for sibling in &self.siblings {
+            current = match sibling {
+                // Only the last node on a level lacks a sibling, and
+                // it's always a left child.
+                None if index & 1 == 0 => parent(params, &[current]),
+                None => return false,
+                Some(sibling) if index & 1 == 0 => parent(params, &[current, *sibling]),
+                Some(sibling) => parent(params, &[*sibling, current]),
+            };
+            index /= 2;
+        }
+
+        index == 0 && current == *root
+    }
+}
+
+impl<P: Borrow<Params>> std::fmt::Debug for MerkleTree<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MerkleTree")
+            .field("params", &self.params().id())
+            .field("block_size", &self.block_size)
+            .field("num_leaves", &self.num_leaves())
+            .field("root", &self.root())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::merkle::MerkleTree;
+    use crate::Params;
+
+    fn data(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 31 % 251) as u8).collect()
+    }
+
+    #[test]
+    fn test_build() {
+        let params = Params::derive(0, b"merkle");
+        let input = data(1000);
+        let tree = MerkleTree::new(&params, 64, &input);
+
+        assert_eq!(tree.num_leaves(), 16);
+        assert_eq!(
+            tree.leaf(15),
+            params.fingerprinter(0).write(&input[960..]).digest()
+        );
+        assert_eq!(
+            MerkleTree::from_reader(&params, 64, &input[..])
+                .expect("must succeed")
+                .root(),
+            tree.root()
+        );
+
+        // Different inputs (including different block sizes) have
+        // different roots.
+        assert_ne!(MerkleTree::new(&params, 128, &input).root(), tree.root());
+        assert_ne!(
+            MerkleTree::new(&params, 64, &input[..999]).root(),
+            tree.root()
+        );
+
+        let empty = MerkleTree::new(&params, 64, b"");
+        assert_eq!(empty.num_leaves(), 1);
+        assert_eq!(
+            MerkleTree::from_reader(&params, 64, &b""[..])
+                .expect("must succeed")
+                .root(),
+            empty.root()
+        );
+    }
+
+    #[test]
+    fn test_update_leaf() {
+        let params = Params::derive(0, b"merkle");
+        let mut input = data(700);
+        let original = MerkleTree::new(&params, 64, &input);
+        let mut tree = original.clone();
+
+        input[300] ^= 1;
+        tree.update_leaf(4, &input[256..320]);
+        assert_eq!(tree.root(), MerkleTree::new(&params, 64, &input).root());
+        assert_eq!(tree.changed_leaves(&original), vec![4]);
+
+        input[650] ^= 1;
+        tree.update_leaf(10, &input[640..]);
+        assert_eq!(tree.root(), MerkleTree::new(&params, 64, &input).root());
+        assert_eq!(tree.changed_leaves(&original), vec![4, 10]);
+        assert!(original.changed_leaves(&original).is_empty());
+
+        // The last block may be shorter, even empty for empty inputs.
+        tree.update_leaf(10, &input[640..641]);
+        assert_eq!(
+            tree.root(),
+            MerkleTree::new(&params, 64, &input[..641]).root()
+        );
+        let mut empty = MerkleTree::new(&params, 64, b"");
+        empty.update_leaf(0, b"x");
+        empty.update_leaf(0, b"");
+        assert_eq!(empty.root(), MerkleTree::new(&params, 64, b"").root());
+    }
+
+    #[test]
+    #[should_panic(expected = "only the last block may be shorter than the block size")]
+    fn test_update_leaf_long_block() {
+        let params = Params::derive(0, b"merkle");
+        let mut tree = MerkleTree::new(&params, 64, &data(700));
+
+        tree.update_leaf(10, &data(65));
+    }
+
+    #[test]
+    #[should_panic(expected = "only the last block may be shorter than the block size")]
+    fn test_update_leaf_short_block() {
+        let params = Params::derive(0, b"merkle");
+        let mut tree = MerkleTree::new(&params, 64, &data(700));
+
+        tree.update_leaf(4, &data(63));
+    }
+
+    #[test]
+    fn test_proofs() {
+        let params = Params::derive(0, b"merkle");
+
+        for num_blocks in 1..=9 {
+            let input = data(16 * num_blocks);
+            let tree = MerkleTree::new(&params, 16, &input);
+            let root = tree.root();
+
+            for (index, block) in input.chunks(16).enumerate() {
+                let proof = tree.proof(index);
+
+                assert!(proof.verify(&params, &root, block));
+                assert!(!proof.verify(&params, &root, &block[1..]));
+
+                let mut moved = proof.clone();
+                moved.index ^= 1;
+                assert!(!moved.verify(&params, &root, block));
+            }
+        }
+    }
+}
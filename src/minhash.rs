@@ -0,0 +1,315 @@
+//! MinHash signatures to estimate the Jaccard similarity of token
+//! sets, and locality-sensitive hashing (LSH) of these signatures
+//! into buckets of likely near-duplicates.
+//!
+//! A [`MinHasher`] with `k` hash functions maps a set of tokens to a
+//! [`Signature`] of `k` values: the minimum hash value over all the
+//! tokens, for each function.  The fraction of positions where two
+//! signatures agree estimates the Jaccard similarity of the two sets.
+//!
+//! # Independence assumptions
+//!
+//! The estimate is unbiased if each function behaves like a random
+//! permutation of the tokens (min-wise independence), and its variance
+//! shrinks as `1/k` if the `k` functions are independent.  UMASH only
+//! proves collision bounds for each [`Params`], not min-wise
+//! independence; in practice, the minimum of UMASH values is well
+//! distributed for non-adversarial inputs.
+//!
+//! Changing the `seed` for a given [`Params`] comes with no proven
+//! bound, so a [`MinHasher`] instead uses `k` different [`Params`],
+//! e.g., derived with [`MinHasher::derive`]: each function then has
+//! its own almost-universal collision bound, and the functions are as
+//! independent as the [`Params::derive`] outputs.  Signatures are only
+//! comparable when computed by the same [`MinHasher`] (or one with the
+//! same [`Params`]).
+use crate::Params;
+use std::collections::{HashMap, HashSet};
+
+/// A [`MinHasher`] computes MinHash [`Signature`]s with one UMASH
+/// function per [`Params`].
+#[derive(Clone, Debug)]
+pub struct MinHasher {
+    params: Vec<Params>,
+}
+
+/// A MinHash [`Signature`] summarises a set of tokens with the minimum
+/// hash value for each of the [`MinHasher`]'s functions.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Signature(pub Vec<u64>);
+
+impl MinHasher {
+    /// Returns a [`MinHasher`] with one hash function for each
+    /// [`Params`] in `params`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `params` is empty.
+    pub fn new(params: Vec<Params>) -> Self {
+        assert!(!params.is_empty(), "MinHash needs at least one function");
+
+        MinHasher { params }
+    }
+
+    /// Returns a [`MinHasher`] with `num_hashes` functions, derived
+    /// deterministically from `key` by calling [`Params::derive`] with
+    /// `bits = 0 .. num_hashes`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_hashes` is zero.
+    pub fn derive(num_hashes: usize, key: &[u8]) -> Self {
+        Self::new(
+            (0..num_hashes as u64)
+                .map(|bits| Params::derive(bits, key))
+                .collect(),
+        )
+    }
+
+    /// Returns the number of hash functions, i.e., the length of each
+    /// [`Signature`].
+    #[inline(always)]
+    pub fn num_hashes(&self) -> usize {
+        self.params.len()
+    }
+
+    /// Computes the [`Signature`] for the set of `tokens`.  Duplicate
+    /// tokens do not affect the result.
+    pub fn signature<I, T>(&self, tokens: I) -> Signature
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        let mut mins = vec![u64::MAX; self.params.len()];
+        let mut hashers: Vec<_> = self.params.iter().map(|params| params.hasher(0)).collect();
+
+        for token in tokens {
+            let token = token.as_ref();
+
+            for (min, hasher) in mins.iter_mut().zip(hashers.iter_mut()) {
+                *min = (*min).min(hasher.reset().write(token).digest());
+            }
+        }
+
+        Signature(mins)
+    }
+
+    /// Splits `signature` in `bands` bands of consecutive values, and
+    /// returns one bucket key for each band.  Two signatures share at
+    /// least one bucket key with probability `1 - (1 - s^r)^bands`,
+    /// where `s` is the Jaccard similarity of their sets, and `r` is
+    /// the number of values per band.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `signature` does not have one value per hash function
+    /// (i.e., [`MinHasher::num_hashes`] values), or if `bands` is
+    /// zero or does not divide the signature's length.
+    pub fn band_keys(&self, signature: &Signature, bands: usize) -> Vec<u64> {
+        assert_eq!(
+            signature.0.len(),
+            self.num_hashes(),
+            "the signature length must match the number of hash functions"
+        );
+        assert!(bands > 0, "there must be at least one band");
+        // `rows > 0`: the signature isn't empty, and `rows * bands`
+        // must match its length.
+        let rows = signature.0.len() / bands;
+        assert_eq!(
+            rows * bands,
+            signature.0.len(),
+            "the number of bands must divide the signature length"
+        );
+
+        signature
+            .0
+            .chunks(rows)
+            .enumerate()
+            .map(|(band, values)| {
+                // Seed with the band index, so that identical values in
+                // different bands map to different buckets.
+                let mut hasher = self.params[0].hasher(band as u64);
+
+                for value in values {
+                    hasher.write(&value.to_le_bytes());
+                }
+
+                hasher.digest()
+            })
+            .collect()
+    }
+}
+
+impl Signature {
+    /// Estimates the Jaccard similarity of the token sets for `self`
+    /// and `other`, as the fraction of positions where the two
+    /// signatures agree.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the two signatures have different lengths.
+    pub fn jaccard(&self, other: &Signature) -> f64 {
+        assert_eq!(
+            self.0.len(),
+            other.0.len(),
+            "signatures must have the same length"
+        );
+
+        if self.0.is_empty() {
+            return 1.0;
+        }
+
+        let equal = self
+            .0
+            .iter()
+            .zip(other.0.iter())
+            .filter(|(x, y)| x == y)
+            .count();
+        equal as f64 / self.0.len() as f64
+    }
+}
+
+/// Returns the approximate Jaccard similarity at which two signatures
+/// have a 50% chance of sharing an LSH bucket, with `bands` bands of
+/// `rows` values each.
+pub fn lsh_threshold(bands: usize, rows: usize) -> f64 {
+    (1.0 / bands as f64).powf(1.0 / rows as f64)
+}
+
+/// An [`LshIndex`] buckets [`Signature`]s by band key, to efficiently
+/// find candidate near-duplicates.
+#[derive(Clone, Debug)]
+pub struct LshIndex<Id> {
+    minhasher: MinHasher,
+    bands: usize,
+    buckets: HashMap<(usize, u64), Vec<Id>>,
+}
+
+impl<Id: Clone + Eq + std::hash::Hash> LshIndex<Id> {
+    /// Returns an empty [`LshIndex`] for signatures computed by
+    /// `minhasher`, split in `bands` bands.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bands` is zero or does not divide the number of
+    /// hash functions in `minhasher`.
+    pub fn new(minhasher: MinHasher, bands: usize) -> Self {
+        assert!(bands > 0, "there must be at least one band");
+        assert_eq!(
+            minhasher.num_hashes() / bands * bands,
+            minhasher.num_hashes(),
+            "the number of bands must divide the signature length"
+        );
+
+        LshIndex {
+            minhasher,
+            bands,
+            buckets: HashMap::new(),
+        }
+    }
+
+    /// Returns the [`MinHasher`] for this index.
+    #[inline(always)]
+    pub fn minhasher(&self) -> &MinHasher {
+        &self.minhasher
+    }
+
+    /// Adds the set identified by `id`, with [`Signature`] `signature`,
+    /// to the index.
+    pub fn insert(&mut self, id: Id, signature: &Signature) {
+        for (band, key) in self
+            .minhasher
+            .band_keys(signature, self.bands)
+            .into_iter()
+            .enumerate()
+        {
+            self.buckets
+                .entry((band, key))
+                .or_default()
+                .push(id.clone());
+        }
+    }
+
+    /// Returns the identifiers of all the sets that share at least one
+    /// bucket with `signature`.
+    pub fn candidates(&self, signature: &Signature) -> HashSet<Id> {
+        self.minhasher
+            .band_keys(signature, self.bands)
+            .into_iter()
+            .enumerate()
+            .filter_map(|(band, key)| self.buckets.get(&(band, key)))
+            .flatten()
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::minhash::{lsh_threshold, LshIndex, MinHasher, Signature};
+
+    fn tokens(range: std::ops::Range<u32>) -> Vec<String> {
+        range.map(|i| format!("token-{}", i)).collect()
+    }
+
+    #[test]
+    fn test_jaccard_estimate() {
+        let minhasher = MinHasher::derive(256, b"minhash");
+
+        // |A & B| / |A | B| = 600 / 1400.
+        let left = minhasher.signature(tokens(0..1000));
+        let right = minhasher.signature(tokens(400..1400));
+        let estimate = left.jaccard(&right);
+
+        assert_eq!(left.0.len(), 256);
+        assert!(
+            (estimate - 600.0 / 1400.0).abs() < 0.1,
+            "estimate {}",
+            estimate
+        );
+
+        // Duplicate tokens and order don't matter.
+        let mut shuffled = tokens(0..1000);
+        shuffled.reverse();
+        shuffled.extend(tokens(0..10));
+        assert_eq!(minhasher.signature(shuffled), left);
+        assert_eq!(left.jaccard(&left), 1.0);
+
+        let disjoint = minhasher.signature(tokens(5000..6000));
+        assert!(left.jaccard(&disjoint) < 0.05);
+    }
+
+    #[test]
+    fn test_lsh() {
+        let minhasher = MinHasher::derive(128, b"minhash");
+        let mut index = LshIndex::new(minhasher.clone(), 32);
+
+        assert!((lsh_threshold(32, 4) - 0.42).abs() < 0.01);
+
+        index.insert("original", &minhasher.signature(tokens(0..1000)));
+        index.insert("unrelated", &minhasher.signature(tokens(2000..3000)));
+
+        let near_duplicate = minhasher.signature(tokens(10..1010));
+        let candidates = index.candidates(&near_duplicate);
+        assert!(candidates.contains("original"));
+        assert!(!candidates.contains("unrelated"));
+
+        let keys = minhasher.band_keys(&near_duplicate, 32);
+        assert_eq!(keys.len(), 32);
+        assert_eq!(keys, minhasher.band_keys(&near_duplicate.clone(), 32));
+    }
+
+    #[test]
+    #[should_panic(expected = "the signature length must match the number of hash functions")]
+    fn test_band_keys_empty_signature() {
+        MinHasher::derive(4, b"minhash").band_keys(&Signature(Vec::new()), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "the number of bands must divide the signature length")]
+    fn test_band_keys_too_many_bands() {
+        let minhasher = MinHasher::derive(4, b"minhash");
+
+        minhasher.band_keys(&minhasher.signature(tokens(0..10)), 8);
+    }
+}
@@ -0,0 +1,241 @@
+//! Computes the primary UMASH hash value for several seeds in one
+//! pass over the input.
+//!
+//! A [`MultiHasher`] is equivalent to one [`Hasher`](crate::Hasher)
+//! per seed; it only saves callers from producing the same input
+//! bytes once per seed.
+use crate::{ffi, Params, UmashComponent};
+
+// Feed bytes to the hashers in chunks of this many bytes, so each
+// chunk stays in L1 while every hasher consumes it.  This is a
+// multiple of the 256-byte UMASH block size.
+const CHUNK_SIZE: usize = 1024;
+
+/// A [`MultiHasher`] computes `N` primary UMASH hash values, one per
+/// seed, for the same input bytes.  Construct one with
+/// [`Params::multi_hasher`].
+///
+/// The hash values are identical to those computed by `N` separate
+/// [`Hasher`](crate::Hasher)s, i.e., [`Params::hasher`] for each seed.
+/// That's because a [`MultiHasher`] *is* `N` separate hashing states: UMASH
+/// does not share work between seeds, so each byte is still hashed
+/// `N` times.  What the [`MultiHasher`] saves is the input traversal:
+/// callers produce (e.g., serialise or read from a file) the input
+/// once, and [`MultiHasher::write`] feeds it to the `N` hashing states
+/// in cache-sized chunks, instead of re-reading the whole input from
+/// memory for each seed.
+///
+/// As with [`Params::hasher`], the seeds tweak the hash values without
+/// any proven impact on collision rates.
+pub struct MultiHasher<'params, const N: usize> {
+    // One `Hasher` state per seed.  We store the plain-old-data
+    // states rather than `[Hasher; N]`, which is awkward to build
+    // without `<[T; N]>::map`.
+    states: [ffi::umash_state; N],
+    params: &'params Params,
+}
+
+impl Params {
+    /// Returns a [`MultiHasher`] that computes the primary UMASH hash
+    /// value for each of the `N` `seeds` in one pass over the input.
+    pub fn multi_hasher<const N: usize>(&self, seeds: &[u64; N]) -> MultiHasher<'_, N> {
+        let mut ret = MultiHasher {
+            states: [unsafe { std::mem::zeroed() }; N],
+            params: self,
+        };
+
+        for (state, seed) in ret.states.iter_mut().zip(seeds.iter()) {
+            unsafe {
+                ffi::umash_init(state, &self.0, *seed, UmashComponent::Hash as i32);
+            }
+        }
+
+        ret
+    }
+}
+
+impl<const N: usize> MultiHasher<'_, N> {
+    /// Updates every hash state by conceptually concatenating `bytes`
+    /// to the hash input.
+    pub fn write(&mut self, bytes: &[u8]) -> &mut Self {
+        for chunk in bytes.chunks(CHUNK_SIZE) {
+            for state in self.states.iter_mut() {
+                unsafe {
+                    ffi::umash_sink_update(
+                        &mut state.sink,
+                        chunk.as_ptr() as *const _,
+                        chunk.len() as u64,
+                    );
+                }
+            }
+        }
+
+        self
+    }
+
+    /// Returns the `N` hash values for the bytes written so far, in
+    /// the same order as the seeds passed to [`Params::multi_hasher`].
+    pub fn digest(&self) -> [u64; N] {
+        let mut ret = [0u64; N];
+
+        for (dst, state) in ret.iter_mut().zip(self.states.iter()) {
+            *dst = unsafe { ffi::umash_digest(state) };
+        }
+
+        ret
+    }
+
+    /// Forgets all the bytes written so far, and brings the
+    /// [`MultiHasher`] back to the state it was in when constructed.
+    pub fn reset(&mut self) -> &mut Self {
+        for state in self.states.iter_mut() {
+            let seed = state.sink.seed;
+
+            // `umash_init` overwrites the whole state.
+            unsafe {
+                ffi::umash_init(state, &self.params.0, seed, UmashComponent::Hash as i32);
+            }
+        }
+
+        self
+    }
+
+    /// Returns the seeds passed to [`Params::multi_hasher`].
+    pub fn seeds(&self) -> [u64; N] {
+        let mut ret = [0u64; N];
+
+        for (dst, state) in ret.iter_mut().zip(self.states.iter()) {
+            *dst = state.sink.seed;
+        }
+
+        ret
+    }
+}
+
+impl<const N: usize> Clone for MultiHasher<'_, N> {
+    fn clone(&self) -> Self {
+        MultiHasher {
+            states: self.states,
+            params: self.params,
+        }
+    }
+}
+
+/// The [`Debug`](std::fmt::Debug) representation of a [`MultiHasher`]
+/// shows the [`ParamsId`](crate::ParamsId) and the seeds, but not the
+/// hashing states.
+impl<const N: usize> std::fmt::Debug for MultiHasher<'_, N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MultiHasher")
+            .field("params", &self.params.id())
+            .field("seeds", &self.seeds())
+            .finish_non_exhaustive()
+    }
+}
+
+/// With the `zeroize` feature, a [`MultiHasher`]'s states are
+/// overwritten with zeros when dropped, as for
+/// [`Hasher`](crate::Hasher).  Explicitly zeroizing a [`MultiHasher`]
+/// wipes the bytes written so far, and leaves it in its initial state,
+/// like [`MultiHasher::reset`].
+#[cfg(feature = "zeroize")]
+impl<const N: usize> zeroize::Zeroize for MultiHasher<'_, N> {
+    fn zeroize(&mut self) {
+        // Save the seeds before wiping them.
+        let seeds = self.seeds();
+
+        // Safety: `umash_state` is plain old data, and is valid when
+        // all zero... and we immediately re-initialise it anyway.
+        unsafe { crate::zeroize_state(&mut self.states) };
+        for (state, seed) in self.states.iter_mut().zip(seeds.iter()) {
+            unsafe {
+                ffi::umash_init(state, &self.params.0, *seed, UmashComponent::Hash as i32);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<const N: usize> zeroize::ZeroizeOnDrop for MultiHasher<'_, N> {}
+
+#[cfg(feature = "zeroize")]
+impl<const N: usize> Drop for MultiHasher<'_, N> {
+    fn drop(&mut self) {
+        // Safety: `umash_state` is plain old data, and is valid when
+        // all zero.
+        unsafe { crate::zeroize_state(&mut self.states) };
+    }
+}
+
+/// A [`MultiHasher`] computes the same hash values for a given
+/// sequence of bytes, regardless of the number of bytes in each
+/// `write` call.
+///
+/// Call [`MultiHasher::digest`] to find the hash values for the
+/// concatenation of all the bytes written to the [`MultiHasher`].
+impl<const N: usize> std::io::Write for MultiHasher<'_, N> {
+    #[inline(always)]
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        Self::write(self, bytes);
+        Ok(bytes.len())
+    }
+
+    /// Flushing a [`MultiHasher`] cannot compute the hash values, due
+    /// to the trait's interface; the implementation is a no-op.
+    ///
+    /// See [`MultiHasher::digest`].
+    #[inline(always)]
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Params;
+
+    #[test]
+    fn test_matches_hashers() {
+        let params = Params::derive(0, b"multi hasher");
+        let seeds = [0, 1, 42, u64::MAX];
+        let input: Vec<u8> = (0..5000u32).map(|i| (i * 7 + i / 256) as u8).collect();
+
+        for len in [0, 1, 8, 16, 17, 255, 256, 1023, 1024, 1025, 5000] {
+            let bytes = &input[..len];
+            let mut expected = [0u64; 4];
+            for (dst, seed) in expected.iter_mut().zip(seeds.iter()) {
+                *dst = params.hasher(*seed).write(bytes).digest();
+            }
+
+            assert_eq!(params.multi_hasher(&seeds).write(bytes).digest(), expected);
+
+            // Split writes don't change anything.
+            let mut multi = params.multi_hasher(&seeds);
+            for chunk in bytes.chunks(100) {
+                std::io::Write::write_all(&mut multi, chunk).expect("must succeed");
+            }
+            assert_eq!(multi.digest(), expected);
+            assert_eq!(multi.reset().write(bytes).digest(), expected);
+            assert_eq!(multi.seeds(), seeds);
+        }
+    }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn test_zeroize() {
+        use zeroize::Zeroize;
+
+        let params = Params::derive(0, b"multi hasher");
+        let seeds = [1, 42];
+        let expected = params
+            .multi_hasher(&seeds)
+            .write(b"the quick brown fox")
+            .digest();
+
+        let mut multi = params.multi_hasher(&seeds);
+        multi.write(b"garbage");
+        multi.zeroize();
+        assert_eq!(multi.seeds(), seeds);
+        assert_eq!(multi.write(b"the quick brown fox").digest(), expected);
+    }
+}
@@ -0,0 +1,158 @@
+//! A [`ParamsSet`] supports gradual key rotation: new fingerprints
+//! are always computed with the active [`Params`], while fingerprints
+//! computed with retired [`Params`] can still be verified until the
+//! retired parameters are forgotten.
+use crate::{Params, ParamsId, TaggedFingerprint};
+
+/// A [`ParamsSet`] holds one active [`Params`], and any number of
+/// retired ones.  Each [`Params`] is identified by its [`ParamsId`],
+/// which is stable for as long as the parameter values are the same.
+#[derive(Clone, Debug)]
+pub struct ParamsSet {
+    active: (ParamsId, Params),
+    retired: Vec<(ParamsId, Params)>,
+}
+
+impl ParamsSet {
+    /// Returns a fresh [`ParamsSet`] with `params` as the active
+    /// parameters, and no retired parameters.
+    pub fn new(params: Params) -> Self {
+        ParamsSet {
+            active: (params.id(), params),
+            retired: Vec::new(),
+        }
+    }
+
+    /// Makes `params` the active parameters, and moves the previously
+    /// active parameters to the retired list.
+    ///
+    /// Rotating to parameters that were previously retired moves them
+    /// out of the retired list.
+    pub fn rotate(&mut self, params: Params) {
+        let id = params.id();
+        let previous = std::mem::replace(&mut self.active, (id, params));
+
+        self.retired.retain(|(retired_id, _)| *retired_id != id);
+        if previous.0 != id {
+            self.retired.push(previous);
+        }
+    }
+
+    /// Drops the retired parameters identified by `id`, and returns
+    /// them if they were present.  The active parameters can only be
+    /// replaced with [`ParamsSet::rotate`].
+    pub fn forget(&mut self, id: ParamsId) -> Option<Params> {
+        let index = self.retired.iter().position(|(x, _)| *x == id)?;
+
+        Some(self.retired.remove(index).1)
+    }
+
+    /// Returns the identifier for the active [`Params`].
+    #[inline(always)]
+    pub fn active_id(&self) -> ParamsId {
+        self.active.0
+    }
+
+    /// Returns the active [`Params`].
+    #[inline(always)]
+    pub fn active(&self) -> &Params {
+        &self.active.1
+    }
+
+    /// Returns the identifiers for the retired [`Params`], from least
+    /// to most recently retired.
+    pub fn retired_ids(&self) -> impl Iterator<Item = ParamsId> + '_ {
+        self.retired.iter().map(|(id, _)| *id)
+    }
+
+    /// Returns the active or retired [`Params`] identified by `id`, if
+    /// any.
+    pub fn get(&self, id: ParamsId) -> Option<&Params> {
+        if self.active.0 == id {
+            return Some(&self.active.1);
+        }
+
+        self.retired
+            .iter()
+            .find(|(x, _)| *x == id)
+            .map(|(_, params)| params)
+    }
+
+    /// Computes the fingerprint of `bytes` for `seed` with the active
+    /// [`Params`], and tags it with the active identifier.
+    pub fn fingerprint(&self, seed: u64, bytes: &[u8]) -> TaggedFingerprint {
+        TaggedFingerprint::new(
+            self.active.0,
+            self.active.1.fingerprinter(seed).write(bytes).digest(),
+        )
+    }
+
+    /// Determines whether `tagged` is the fingerprint of `bytes` for
+    /// `seed`, with the active or retired [`Params`] identified by
+    /// `tagged.params_id`.
+    ///
+    /// Returns false if the identifier is unknown.
+    pub fn verify(&self, seed: u64, tagged: &TaggedFingerprint, bytes: &[u8]) -> bool {
+        match self.get(tagged.params_id) {
+            Some(params) => params.fingerprinter(seed).write(bytes).digest() == tagged.fingerprint,
+            None => false,
+        }
+    }
+
+    /// Determines whether `tagged` was computed with the active
+    /// [`Params`].  Fingerprints computed with retired parameters
+    /// should eventually be recomputed with [`ParamsSet::fingerprint`].
+    #[inline(always)]
+    pub fn is_active(&self, tagged: &TaggedFingerprint) -> bool {
+        tagged.params_id == self.active.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Params, ParamsSet};
+
+    #[test]
+    fn test_rotation() {
+        let first = Params::derive(1, b"key");
+        let second = Params::derive(2, b"key");
+        let mut set = ParamsSet::new(first.clone());
+        let old = set.fingerprint(42, b"the quick brown fox");
+
+        assert_eq!(old.params_id, first.id());
+        assert!(set.is_active(&old));
+        assert!(set.verify(42, &old, b"the quick brown fox"));
+        assert!(!set.verify(42, &old, b"the quick brown dog"));
+        assert!(!set.verify(43, &old, b"the quick brown fox"));
+
+        set.rotate(second.clone());
+        let new = set.fingerprint(42, b"the quick brown fox");
+
+        assert_eq!(set.active_id(), second.id());
+        assert_eq!(set.retired_ids().collect::<Vec<_>>(), vec![first.id()]);
+        assert_eq!(new.params_id, second.id());
+        assert_ne!(new.fingerprint, old.fingerprint);
+        assert!(!set.is_active(&old));
+        assert!(set.verify(42, &old, b"the quick brown fox"));
+        assert!(set.verify(42, &new, b"the quick brown fox"));
+
+        assert!(set.forget(first.id()) == Some(first));
+        assert!(set.forget(second.id()).is_none());
+        assert!(!set.verify(42, &old, b"the quick brown fox"));
+        assert!(set.verify(42, &new, b"the quick brown fox"));
+    }
+
+    #[test]
+    fn test_rotate_back() {
+        let first = Params::derive(1, b"key");
+        let second = Params::derive(2, b"key");
+        let mut set = ParamsSet::new(first.clone());
+
+        set.rotate(second.clone());
+        set.rotate(first.clone());
+
+        assert_eq!(set.active_id(), first.id());
+        assert!(set.active() == &first);
+        assert_eq!(set.retired_ids().collect::<Vec<_>>(), vec![second.id()]);
+    }
+}
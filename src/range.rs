@@ -0,0 +1,107 @@
+//! Helpers to map 64-bit hash values, e.g., from [`Params::hash`] or
+//! [`Hasher::digest`], to ranges, floating point values, and sampling
+//! decisions.
+//!
+//! These functions are deterministic and only depend on the hash
+//! value, so services that share [`Params`] (e.g., from
+//! [`Params::derive`]) agree on bucket assignments and sampling
+//! decisions.
+//!
+//! [`Params`]: crate::Params
+//! [`Params::hash`]: crate::Params::hash
+//! [`Params::derive`]: crate::Params::derive
+//! [`Hasher::digest`]: crate::Hasher::digest
+
+/// Maps `hash` to `[0, n)` with Lemire's multiply-shift reduction:
+/// `(hash * n) >> 64`.
+///
+/// When `hash` is uniformly distributed, each value in `[0, n)` has
+/// probability within `1 / 2^64` of `1 / n`.  That's the same bias
+/// as `hash % n`, but without a division, and with a result that
+/// depends on the high bits of `hash`.
+///
+/// # Panics
+///
+/// Panics if `n` is zero.
+#[inline(always)]
+pub fn hash_to_range(hash: u64, n: u64) -> u64 {
+    assert!(n > 0, "the range must not be empty");
+
+    ((hash as u128 * n as u128) >> 64) as u64
+}
+
+/// Maps `hash` to a uniformly distributed `f64` in `[0, 1)`, from its
+/// 53 most significant bits.
+#[inline(always)]
+pub fn hash_to_unit_f64(hash: u64) -> f64 {
+    (hash >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+}
+
+/// Determines whether to sample the item with hash value `hash`, when
+/// sampling a fraction `rate` of all items.
+///
+/// The decision is monotonic in `rate`: an item sampled at a given
+/// rate is also sampled at all higher rates.  A `rate` of 0 (or NaN)
+/// never samples, and a `rate` of 1 or more always does.
+#[inline(always)]
+pub fn sample(hash: u64, rate: f64) -> bool {
+    hash_to_unit_f64(hash) < rate
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::range::{hash_to_range, hash_to_unit_f64, sample};
+    use crate::Params;
+
+    #[test]
+    fn test_hash_to_range() {
+        assert_eq!(hash_to_range(0, 10), 0);
+        assert_eq!(hash_to_range(u64::MAX, 10), 9);
+        assert_eq!(hash_to_range(1 << 63, 10), 5);
+        assert_eq!(hash_to_range(u64::MAX, 1), 0);
+        assert_eq!(hash_to_range(12345, u64::MAX), 12344);
+
+        let params = Params::derive(0, b"range");
+        let mut counts = [0u32; 7];
+        for i in 0..7000u32 {
+            counts[params.hash_to_range(i, 7) as usize] += 1;
+        }
+
+        assert!(counts.iter().all(|&n| n > 850 && n < 1150), "{:?}", counts);
+    }
+
+    #[test]
+    fn test_unit_f64() {
+        assert_eq!(hash_to_unit_f64(0), 0.0);
+        assert_eq!(hash_to_unit_f64(1 << 63), 0.5);
+        assert!(hash_to_unit_f64(u64::MAX) < 1.0);
+
+        let params = Params::derive(0, b"range");
+        let mean = (0..10_000u32)
+            .map(|i| params.hash_to_unit_f64(i))
+            .sum::<f64>()
+            / 10_000.0;
+        assert!((mean - 0.5).abs() < 0.02, "{}", mean);
+    }
+
+    #[test]
+    fn test_sample() {
+        assert!(!sample(0, 0.0));
+        assert!(!sample(0, f64::NAN));
+        assert!(sample(u64::MAX, 1.0));
+        assert!(sample(1 << 63, 0.51));
+        assert!(!sample(1 << 63, 0.5));
+
+        let params = Params::derive(0, b"range");
+        let mut sampled = 0;
+        for i in 0..10_000u32 {
+            if params.sample(i, 0.1) {
+                sampled += 1;
+                // Monotonic in the rate.
+                assert!(params.sample(i, 0.2));
+            }
+        }
+
+        assert!(sampled > 850 && sampled < 1150, "{}", sampled);
+    }
+}
@@ -0,0 +1,405 @@
+//! Consistent assignment of keys to shards: when shards are added or
+//! removed, only a small fraction of keys move.
+//!
+//! This module offers three classic schemes, all keyed by a
+//! [`Params`]:
+//!
+//! - [`JumpHash`] (Lamping and Veach's jump consistent hash) maps keys
+//!   to numbered buckets `0 .. n` in constant space, but only supports
+//!   adding or removing the last bucket;
+//! - [`Rendezvous`] (highest random weight) hashing picks the node with
+//!   the highest score for each key, in time linear in the number of
+//!   nodes, and supports arbitrary node removals;
+//! - [`HashRing`] is a ketama-style ring of virtual nodes, with
+//!   logarithmic-time lookups.
+//!
+//! Keys and node identifiers are hashed with [`std::hash::Hash`], so
+//! assignments are stable across processes when the [`Params`] are,
+//! e.g., when they come from [`Params::derive`].
+use crate::Params;
+use std::borrow::Borrow;
+use std::hash::Hash;
+
+/// Returns the bucket in `0 .. num_buckets` for `key`, with Lamping and
+/// Veach's jump consistent hash.  When `num_buckets` grows by one, a
+/// key either stays in its bucket, or moves to the new last bucket.
+///
+/// # Panics
+///
+/// Panics if `num_buckets` is zero.
+pub fn jump_consistent_hash(mut key: u64, num_buckets: u32) -> u32 {
+    assert!(num_buckets > 0, "there must be at least one bucket");
+
+    let mut bucket: i64 = -1;
+    let mut next: i64 = 0;
+    while next < num_buckets as i64 {
+        bucket = next;
+        key = key.wrapping_mul(2862933555777941757).wrapping_add(1);
+        next = ((bucket + 1) as f64 * ((1u64 << 31) as f64 / ((key >> 33) + 1) as f64)) as i64;
+    }
+
+    bucket as u32
+}
+
+/// A [`JumpHash`] maps keys to `num_buckets` numbered buckets with
+/// [`jump_consistent_hash`] on their UMASH hash value.
+///
+/// See the [crate docs](crate#data-structures) for the choice of
+/// `P`.
+#[derive(Clone)]
+pub struct JumpHash<P: Borrow<Params>> {
+    params: P,
+    num_buckets: u32,
+}
+
+impl<P: Borrow<Params>> JumpHash<P> {
+    /// Returns a [`JumpHash`] for `num_buckets` buckets.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_buckets` is zero.
+    pub fn new(params: P, num_buckets: u32) -> Self {
+        assert!(num_buckets > 0, "there must be at least one bucket");
+
+        JumpHash {
+            params,
+            num_buckets,
+        }
+    }
+
+    /// Returns the [`Params`] for this [`JumpHash`].
+    #[inline(always)]
+    pub fn params(&self) -> &Params {
+        self.params.borrow()
+    }
+
+    /// Returns the number of buckets.
+    #[inline(always)]
+    pub fn num_buckets(&self) -> u32 {
+        self.num_buckets
+    }
+
+    /// Returns the bucket in `0 .. num_buckets` for `key`.
+    pub fn bucket(&self, key: impl Hash) -> u32 {
+        jump_consistent_hash(self.params().hash(key), self.num_buckets)
+    }
+}
+
+impl<P: Borrow<Params>> std::fmt::Debug for JumpHash<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JumpHash")
+            .field("params", &self.params().id())
+            .field("num_buckets", &self.num_buckets)
+            .finish()
+    }
+}
+
+/// A [`Rendezvous`] hashes keys to nodes with highest random weight
+/// hashing: each key goes to the node with the highest score for that
+/// key.  Removing a node only moves the keys that were assigned to it,
+/// and adding a node only moves keys to the new node.
+#[derive(Clone)]
+pub struct Rendezvous<P: Borrow<Params>, N> {
+    params: P,
+    // Each node, with the hash of its identifier.
+    nodes: Vec<(N, u64)>,
+}
+
+impl<P: Borrow<Params>, N: Hash + Eq> Rendezvous<P, N> {
+    /// Returns a [`Rendezvous`] without any node.
+    pub fn new(params: P) -> Self {
+        Rendezvous {
+            params,
+            nodes: Vec::new(),
+        }
+    }
+
+    /// Returns the [`Params`] for this [`Rendezvous`].
+    #[inline(always)]
+    pub fn params(&self) -> &Params {
+        self.params.borrow()
+    }
+
+    /// Returns the nodes, in insertion order.
+    pub fn nodes(&self) -> impl Iterator<Item = &N> + '_ {
+        self.nodes.iter().map(|(node, _)| node)
+    }
+
+    /// Adds `node`, and returns whether it was absent.
+    pub fn add(&mut self, node: N) -> bool {
+        if self.nodes.iter().any(|(x, _)| *x == node) {
+            return false;
+        }
+
+        let hash = self.params().hash(&node);
+        self.nodes.push((node, hash));
+        true
+    }
+
+    /// Removes `node`, and returns whether it was present.
+    pub fn remove(&mut self, node: &N) -> bool {
+        let len = self.nodes.len();
+
+        self.nodes.retain(|(x, _)| x != node);
+        self.nodes.len() != len
+    }
+
+    /// Returns each node's score for `key`.
+    fn scores(&self, key: impl Hash) -> impl Iterator<Item = (u64, &N)> + '_ {
+        let key = self.params().hash(key).to_le_bytes();
+        let mut hasher = self.params().hasher(0);
+
+        self.nodes.iter().map(move |(node, hash)| {
+            let score = hasher
+                .reset()
+                .write(&hash.to_le_bytes())
+                .write(&key)
+                .digest();
+            (score, node)
+        })
+    }
+
+    /// Returns the node for `key`, or `None` if there is no node.
+    pub fn node_for(&self, key: impl Hash) -> Option<&N> {
+        self.scores(key)
+            .max_by_key(|(score, _)| *score)
+            .map(|(_, node)| node)
+    }
+
+    /// Returns all the nodes, in decreasing order of preference for
+    /// `key`: the first `r` nodes are a stable choice of `r` replicas.
+    pub fn ranked(&self, key: impl Hash) -> Vec<&N> {
+        let mut scores: Vec<_> = self.scores(key).collect();
+
+        scores.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        scores.into_iter().map(|(_, node)| node).collect()
+    }
+}
+
+impl<P: Borrow<Params>, N> std::fmt::Debug for Rendezvous<P, N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Rendezvous")
+            .field("params", &self.params.borrow().id())
+            .field("num_nodes", &self.nodes.len())
+            .finish()
+    }
+}
+
+/// A [`HashRing`] is a ketama-style consistent hashing ring: each node
+/// owns `replicas` virtual points on a 64-bit ring, and each key goes
+/// to the node that owns the first point at or after the key's hash
+/// value.  More virtual points per node improve balance, at the
+/// expense of memory.
+#[derive(Clone)]
+pub struct HashRing<P: Borrow<Params>, N> {
+    params: P,
+    replicas: u32,
+    nodes: Vec<N>,
+    // Sorted `(point, index in nodes)` pairs.
+    ring: Vec<(u64, usize)>,
+}
+
+impl<P: Borrow<Params>, N: Hash + Eq> HashRing<P, N> {
+    /// Returns an empty [`HashRing`], with `replicas` virtual points
+    /// per node.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `replicas` is zero.
+    pub fn new(params: P, replicas: u32) -> Self {
+        assert!(replicas > 0, "each node needs at least one point");
+
+        HashRing {
+            params,
+            replicas,
+            nodes: Vec::new(),
+            ring: Vec::new(),
+        }
+    }
+
+    /// Returns the [`Params`] for this [`HashRing`].
+    #[inline(always)]
+    pub fn params(&self) -> &Params {
+        self.params.borrow()
+    }
+
+    /// Returns the number of virtual points per node.
+    #[inline(always)]
+    pub fn replicas(&self) -> u32 {
+        self.replicas
+    }
+
+    /// Returns the nodes, in insertion order.
+    pub fn nodes(&self) -> impl Iterator<Item = &N> + '_ {
+        self.nodes.iter()
+    }
+
+    /// Adds `node`, and returns whether it was absent.
+    pub fn add(&mut self, node: N) -> bool {
+        if self.nodes.contains(&node) {
+            return false;
+        }
+
+        let index = self.nodes.len();
+        let mut hasher = self.params.borrow().hasher(0);
+        for replica in 0..self.replicas {
+            hasher.reset();
+            (&node, replica).hash(&mut hasher);
+            self.ring.push((hasher.digest(), index));
+        }
+
+        self.nodes.push(node);
+        self.ring.sort_unstable();
+        true
+    }
+
+    /// Removes `node`, and returns whether it was present.
+    pub fn remove(&mut self, node: &N) -> bool {
+        let index = match self.nodes.iter().position(|x| x == node) {
+            Some(index) => index,
+            None => return false,
+        };
+
+        self.nodes.remove(index);
+        self.ring.retain(|(_, x)| *x != index);
+        for (_, x) in self.ring.iter_mut() {
+            if *x > index {
+                *x -= 1;
+            }
+        }
+
+        true
+    }
+
+    /// Returns the node for `key`, or `None` if the ring is empty.
+    pub fn node_for(&self, key: impl Hash) -> Option<&N> {
+        if self.ring.is_empty() {
+            return None;
+        }
+
+        let hash = self.params().hash(key);
+        let pos = self.ring.partition_point(|(point, _)| *point < hash);
+        // Wrap around past the last point.
+        let (_, index) = self.ring[pos % self.ring.len()];
+        Some(&self.nodes[index])
+    }
+}
+
+impl<P: Borrow<Params>, N> std::fmt::Debug for HashRing<P, N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HashRing")
+            .field("params", &self.params.borrow().id())
+            .field("replicas", &self.replicas)
+            .field("num_nodes", &self.nodes.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::shard::{jump_consistent_hash, HashRing, JumpHash, Rendezvous};
+    use crate::Params;
+
+    const NUM_KEYS: u64 = 10_000;
+
+    #[test]
+    fn test_jump_hash() {
+        let params = Params::derive(0, b"shard");
+        let before = JumpHash::new(&params, 10);
+        let after = JumpHash::new(&params, 11);
+        let mut counts = [0u64; 10];
+        let mut moved = 0;
+
+        assert_eq!(jump_consistent_hash(42, 1), 0);
+        for key in 0..NUM_KEYS {
+            let old = before.bucket(key);
+            let new = after.bucket(key);
+
+            counts[old as usize] += 1;
+            if old != new {
+                // Keys only move to the new bucket.
+                assert_eq!(new, 10);
+                moved += 1;
+            }
+        }
+
+        // We expect 1/11th of the keys to move.
+        assert!(moved > 700 && moved < 1100, "{} keys moved", moved);
+        assert!(counts.iter().all(|&n| n > 800 && n < 1200), "{:?}", counts);
+    }
+
+    #[test]
+    fn test_rendezvous() {
+        let params = Params::derive(0, b"shard");
+        let mut hrw = Rendezvous::new(&params);
+
+        assert_eq!(hrw.node_for(0u64), None);
+        for node in 0..10 {
+            assert!(hrw.add(format!("node-{}", node)));
+        }
+
+        assert!(!hrw.add("node-0".to_string()));
+
+        let before: Vec<_> = (0..NUM_KEYS)
+            .map(|key| hrw.node_for(key).unwrap().clone())
+            .collect();
+        assert!(hrw.remove(&"node-3".to_string()));
+        assert!(!hrw.remove(&"node-3".to_string()));
+
+        for (key, old) in (0..NUM_KEYS).zip(before.iter()) {
+            let new = hrw.node_for(key).unwrap();
+
+            // Only the removed node's keys move.
+            assert!(old == "node-3" || old == new);
+        }
+
+        let moved = before.iter().filter(|node| *node == "node-3").count();
+        assert!(moved > 800 && moved < 1200, "{} keys moved", moved);
+
+        let ranked = hrw.ranked(123u64);
+        assert_eq!(ranked.len(), 9);
+        assert_eq!(Some(ranked[0]), hrw.node_for(123u64));
+    }
+
+    #[test]
+    fn test_ring() {
+        let params = Params::derive(0, b"shard");
+        let mut ring = HashRing::new(&params, 100);
+
+        assert_eq!(ring.node_for(0u64), None);
+        for node in 0..10 {
+            assert!(ring.add(node));
+        }
+
+        let before: Vec<_> = (0..NUM_KEYS)
+            .map(|key| *ring.node_for(key).unwrap())
+            .collect();
+        let mut counts = [0u64; 10];
+        for node in before.iter() {
+            counts[*node as usize] += 1;
+        }
+
+        assert!(counts.iter().all(|&n| n > 600 && n < 1400), "{:?}", counts);
+
+        assert!(ring.add(10));
+        let mut moved = 0;
+        for (key, old) in (0..NUM_KEYS).zip(before.iter()) {
+            let new = *ring.node_for(key).unwrap();
+
+            if new != *old {
+                // Keys only move to the new node.
+                assert_eq!(new, 10);
+                moved += 1;
+            }
+        }
+
+        assert!(moved > 500 && moved < 1400, "{} keys moved", moved);
+
+        // Removing the new node restores the original assignment.
+        assert!(ring.remove(&10));
+        assert!(!ring.remove(&10));
+        for (key, old) in (0..NUM_KEYS).zip(before.iter()) {
+            assert_eq!(ring.node_for(key), Some(old));
+        }
+    }
+}
@@ -0,0 +1,240 @@
+//! SimHash locality-sensitive fingerprints: similar sets of weighted
+//! features map to fingerprints with a small Hamming distance.
+//!
+//! Each feature is hashed with the [`SimHasher`]'s [`Params`] (the
+//! primary [`UmashComponent::Hash`](crate::UmashComponent::Hash) for
+//! 64-bit simhashes, the full [`Fingerprint`](crate::Fingerprint) for
+//! 128-bit ones), and every bit of the result adds or subtracts the
+//! feature's weight to a per-bit accumulator.  The simhash has a bit
+//! set wherever the accumulator is positive.
+//!
+//! Unlike UMASH [`Fingerprint`](crate::Fingerprint)s, simhashes offer
+//! no collision bound: they're meant to find near-duplicates, not to
+//! tell different inputs apart.
+use crate::Params;
+use std::borrow::Borrow;
+use std::collections::HashMap;
+
+/// A 64-bit simhash.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SimHash64(pub u64);
+
+/// A 128-bit simhash; the first `u64` is computed from the primary
+/// UMASH hash value of each feature, and the second from the secondary
+/// value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SimHash128(pub [u64; 2]);
+
+impl SimHash64 {
+    /// Returns the number of bits that differ in `self` and `other`.
+    #[inline(always)]
+    pub fn hamming_distance(&self, other: &SimHash64) -> u32 {
+        (self.0 ^ other.0).count_ones()
+    }
+
+    /// Returns the fraction of bits that are equal in `self` and
+    /// `other`, between 0 and 1.
+    pub fn similarity(&self, other: &SimHash64) -> f64 {
+        1.0 - self.hamming_distance(other) as f64 / 64.0
+    }
+}
+
+impl SimHash128 {
+    /// Returns the number of bits that differ in `self` and `other`.
+    #[inline(always)]
+    pub fn hamming_distance(&self, other: &SimHash128) -> u32 {
+        (self.0[0] ^ other.0[0]).count_ones() + (self.0[1] ^ other.0[1]).count_ones()
+    }
+
+    /// Returns the fraction of bits that are equal in `self` and
+    /// `other`, between 0 and 1.
+    pub fn similarity(&self, other: &SimHash128) -> f64 {
+        1.0 - self.hamming_distance(other) as f64 / 128.0
+    }
+}
+
+/// Splits `text` into lowercase alphanumeric words.
+pub fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+}
+
+/// Returns the words in `text`, weighted by their number of
+/// occurrences.
+fn term_frequencies(text: &str) -> HashMap<String, f64> {
+    let mut ret = HashMap::new();
+
+    for word in tokenize(text) {
+        *ret.entry(word).or_insert(0.0) += 1.0;
+    }
+
+    ret
+}
+
+/// Adds `weight` to `acc[i]` if bit `i` of `hash` is set, and
+/// subtracts it otherwise.
+#[inline(always)]
+fn accumulate(acc: &mut [f64], hash: u64, weight: f64) {
+    for (i, slot) in acc.iter_mut().enumerate() {
+        if (hash >> i) & 1 != 0 {
+            *slot += weight;
+        } else {
+            *slot -= weight;
+        }
+    }
+}
+
+/// Returns a `u64` with bit `i` set iff `acc[i]` is positive.
+#[inline(always)]
+fn collapse(acc: &[f64]) -> u64 {
+    acc.iter()
+        .enumerate()
+        .filter(|(_, x)| **x > 0.0)
+        .fold(0, |ret, (i, _)| ret | (1 << i))
+}
+
+/// A [`SimHasher`] computes [`SimHash64`] and [`SimHash128`] values
+/// with a given [`Params`] and seed.
+///
+/// See the [crate docs](crate#data-structures) for the choice of
+/// `P`.
+#[derive(Clone, Debug)]
+pub struct SimHasher<P: Borrow<Params>> {
+    params: P,
+    seed: u64,
+}
+
+impl<P: Borrow<Params>> SimHasher<P> {
+    /// Returns a [`SimHasher`] for `params` and `seed = 0`.
+    pub fn new(params: P) -> Self {
+        Self::with_seed(params, 0)
+    }
+
+    /// Returns a [`SimHasher`] for `params` and `seed`.
+    pub fn with_seed(params: P, seed: u64) -> Self {
+        SimHasher { params, seed }
+    }
+
+    /// Returns the [`Params`] for this hasher.
+    #[inline(always)]
+    pub fn params(&self) -> &Params {
+        self.params.borrow()
+    }
+
+    /// Computes the 64-bit simhash of `features`, a sequence of
+    /// `(feature bytes, weight)` pairs.  Repeated features count once
+    /// per occurrence.
+    pub fn simhash64<I, T>(&self, features: I) -> SimHash64
+    where
+        I: IntoIterator<Item = (T, f64)>,
+        T: AsRef<[u8]>,
+    {
+        let mut acc = [0f64; 64];
+        let mut hasher = self.params().hasher(self.seed);
+
+        for (feature, weight) in features {
+            accumulate(
+                &mut acc,
+                hasher.reset().write(feature.as_ref()).digest(),
+                weight,
+            );
+        }
+
+        SimHash64(collapse(&acc))
+    }
+
+    /// Computes the 128-bit simhash of `features`, a sequence of
+    /// `(feature bytes, weight)` pairs.  Repeated features count once
+    /// per occurrence.
+    pub fn simhash128<I, T>(&self, features: I) -> SimHash128
+    where
+        I: IntoIterator<Item = (T, f64)>,
+        T: AsRef<[u8]>,
+    {
+        let mut acc = [[0f64; 64]; 2];
+        let mut fingerprinter = self.params().fingerprinter(self.seed);
+
+        for (feature, weight) in features {
+            let fprint = fingerprinter.reset().write(feature.as_ref()).digest();
+
+            accumulate(&mut acc[0], fprint.hash(), weight);
+            accumulate(&mut acc[1], fprint.secondary(), weight);
+        }
+
+        SimHash128([collapse(&acc[0]), collapse(&acc[1])])
+    }
+
+    /// Computes the 64-bit simhash of the words in `text`, weighted by
+    /// their number of occurrences.  See [`tokenize`].
+    pub fn text64(&self, text: &str) -> SimHash64 {
+        self.simhash64(term_frequencies(text))
+    }
+
+    /// Computes the 128-bit simhash of the words in `text`, weighted
+    /// by their number of occurrences.  See [`tokenize`].
+    pub fn text128(&self, text: &str) -> SimHash128 {
+        self.simhash128(term_frequencies(text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::simhash::{tokenize, SimHash64, SimHasher};
+    use crate::Params;
+
+    const TEXT: &str = "UMASH is an almost-universal family of hash functions. \
+        Each Params struct defines a specific hash function; when the \
+        parameters are generated pseudorandomly, the probability that two \
+        different inputs of up to s bytes collide is at most ceil(s / 4096) \
+        2^-55 for the 64-bit hash. The 128-bit fingerprint reduces that \
+        probability to less than 2^-70 for inputs of 1 GB or less.";
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize("Hello, world! hello-again").collect::<Vec<_>>(),
+            vec!["hello", "world", "hello", "again"]
+        );
+    }
+
+    #[test]
+    fn test_similar_texts() {
+        let params = Params::derive(0, b"simhash");
+        let simhasher = SimHasher::new(&params);
+        let edited = TEXT.replace("specific", "particular");
+        let unrelated = "The quick brown fox jumps over the lazy dog, \
+            while a slow purple turtle watches from behind an oak tree.";
+
+        let original = simhasher.text64(TEXT);
+        assert_eq!(original.hamming_distance(&simhasher.text64(TEXT)), 0);
+        assert!(original.hamming_distance(&simhasher.text64(&edited)) <= 10);
+        assert!(original.hamming_distance(&simhasher.text64(unrelated)) >= 16);
+
+        let original = simhasher.text128(TEXT);
+        assert_eq!(original.similarity(&simhasher.text128(TEXT)), 1.0);
+        assert!(original.hamming_distance(&simhasher.text128(&edited)) <= 20);
+        assert!(original.hamming_distance(&simhasher.text128(unrelated)) >= 32);
+    }
+
+    #[test]
+    fn test_weights() {
+        let params = Params::derive(0, b"simhash");
+        let simhasher = SimHasher::with_seed(&params, 1);
+        let heavy = simhasher.simhash64(vec![("foo", 10.0), ("bar", 1.0)]);
+
+        // A single dominant feature determines the simhash.
+        assert_eq!(heavy, SimHash64(params.hasher(1).write(b"foo").digest()));
+        assert_eq!(heavy.similarity(&heavy), 1.0);
+        assert_eq!(
+            simhasher.simhash64(vec![("foo", 1.0), ("foo", 1.0)]),
+            simhasher.simhash64(vec![("foo", 2.0)])
+        );
+        assert_eq!(
+            simhasher
+                .simhash128(Vec::<(&str, f64)>::new())
+                .hamming_distance(&simhasher.simhash128(vec![("foo", 1.0), ("foo", -1.0)])),
+            0
+        );
+    }
+}
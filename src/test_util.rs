@@ -0,0 +1,12 @@
+//! Helpers shared by the unit tests.
+use std::path::PathBuf;
+
+/// Returns a fresh, empty scratch directory for `name`, unique to this
+/// process.  Callers should remove the directory when done.
+pub(crate) fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("umash-{}-{}", name, std::process::id()));
+
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("must create scratch dir");
+    dir
+}
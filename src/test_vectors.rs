@@ -0,0 +1,696 @@
+//! Known-answer test vectors for UMASH, to confirm at runtime that
+//! the linked `umash-sys` computes the same values as the reference
+//! implementation.
+//!
+//! Call [`verify_test_vectors`] at startup before persisting or
+//! comparing hash and fingerprint values across processes.
+//!
+//! The vectors are versioned ([`VERSION`]): a given version's table
+//! never changes, since UMASH values for [`Params::derive`]d
+//! parameters are stable.  The table covers:
+//!
+//! - five sets of parameters, derived from different `bits` values
+//!   and keys (empty, short, and longer than 32 bytes);
+//! - the seeds 0, 1, 42, and `u64::MAX`;
+//! - every input length from 0 to 64 bytes, which covers UMASH's
+//!   short (at most 8 bytes) and medium (at most 16 bytes) input
+//!   paths, and lengths on either side of every power of two from 128
+//!   to 8192 bytes, to cross the 256-byte block and 4 KiB boundaries.
+//!
+//! Each vector lists the primary [`UmashComponent::Hash`] and the
+//! [`UmashComponent::Secondary`] values for [`input`]`(len)`; the
+//! [`Fingerprint`] is that pair of values.
+use crate::{ffi, Fingerprint, Params, UmashComponent};
+
+/// The version of the test vector table.
+pub const VERSION: u32 = 1;
+
+/// The `(bits, key)` arguments to [`Params::derive`] for the vectors.
+const PARAMS: [(u64, &[u8]); 5] = [
+    (0, b""),
+    (0, b"hello example.c"),
+    (1, b"hello example.c"),
+    (u64::MAX, b"umash test vectors"),
+    (
+        0x5eed,
+        b"a key that is longer than the 32 bytes that UMASH derivation consumes",
+    ),
+];
+
+/// A [`TestVector`] is the expected output of UMASH for one set of
+/// derived [`Params`], a seed, and [`input`]`(len)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TestVector {
+    /// The `bits` argument to [`Params::derive`].
+    pub bits: u64,
+
+    /// The `key` argument to [`Params::derive`].
+    pub key: &'static [u8],
+
+    /// The seed for the hash and fingerprint.
+    pub seed: u64,
+
+    /// The length of the [`input`].
+    pub len: usize,
+
+    /// The expected [`UmashComponent::Hash`] value.
+    pub hash: u64,
+
+    /// The expected [`UmashComponent::Secondary`] value.
+    pub secondary: u64,
+}
+
+impl TestVector {
+    /// Returns the expected [`Fingerprint`].
+    pub fn fingerprint(&self) -> Fingerprint {
+        Fingerprint::new(self.hash, self.secondary)
+    }
+
+    /// Returns the input bytes for this vector.
+    pub fn input(&self) -> Vec<u8> {
+        input(self.len)
+    }
+}
+
+/// Returns the `len` input bytes for test vectors.
+///
+/// The bytes are the little-endian SplitMix64 values for an initial
+/// state of `len`, truncated to `len` bytes.
+pub fn input(len: usize) -> Vec<u8> {
+    let mut state = len as u64;
+    let mut ret = Vec::with_capacity(len + 8);
+
+    while ret.len() < len {
+        state = state.wrapping_add(0x9e3779b97f4a7c15);
+
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        ret.extend_from_slice(&(z ^ (z >> 31)).to_le_bytes());
+    }
+
+    ret.truncate(len);
+    ret
+}
+
+/// Returns an iterator over all the test vectors.
+pub fn vectors() -> impl Iterator<Item = TestVector> {
+    TABLE
+        .iter()
+        .map(|&(params, seed, len, hash, secondary)| TestVector {
+            bits: PARAMS[params].0,
+            key: PARAMS[params].1,
+            seed,
+            len,
+            hash,
+            secondary,
+        })
+}
+
+/// A [`TestVectorMismatch`] describes the first [`TestVector`] for
+/// which the linked UMASH implementation computed an unexpected value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TestVectorMismatch {
+    /// The failed test vector.
+    pub vector: TestVector,
+
+    /// The code path that computed the wrong value, e.g., `"Hasher"`.
+    pub check: &'static str,
+}
+
+impl std::fmt::Display for TestVectorMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "UMASH test vector (version {}) mismatch for {}: bits={:#x} key={:?} seed={:#x} len={}",
+            VERSION,
+            self.check,
+            self.vector.bits,
+            String::from_utf8_lossy(self.vector.key),
+            self.vector.seed,
+            self.vector.len
+        )
+    }
+}
+
+impl std::error::Error for TestVectorMismatch {}
+
+/// Checks every test vector against the one-shot and streaming
+/// (with the input split in several writes) [`Hasher`](crate::Hasher)
+/// and [`Fingerprinter`](crate::Fingerprinter) code paths, and returns
+/// the first mismatch, if any.
+pub fn verify_test_vectors() -> Result<(), TestVectorMismatch> {
+    let params: Vec<Params> = PARAMS
+        .iter()
+        .map(|(bits, key)| Params::derive(*bits, key))
+        .collect();
+
+    for (&(index, ..), vector) in TABLE.iter().zip(vectors()) {
+        let params = &params[index];
+        let seed = vector.seed;
+        let input = vector.input();
+        let expected = vector.fingerprint();
+        let mismatch = |check| Err(TestVectorMismatch { vector, check });
+
+        let one_shot = |which: UmashComponent| unsafe {
+            ffi::umash_full(
+                &params.0,
+                seed,
+                which as i32,
+                input.as_ptr() as *const _,
+                input.len() as u64,
+            )
+        };
+        if one_shot(UmashComponent::Hash) != vector.hash {
+            return mismatch("umash_full (hash)");
+        }
+
+        if one_shot(UmashComponent::Secondary) != vector.secondary {
+            return mismatch("umash_full (secondary)");
+        }
+
+        if !params.verify(seed, &expected, &input).full {
+            return mismatch("umash_fprint");
+        }
+
+        // Split the input in uneven writes, to exercise the streaming
+        // buffers.
+        let (head, tail) = input.split_at(input.len() / 3);
+        let mut hasher = params.hasher(seed);
+        if hasher.write(head).write(tail).digest() != vector.hash {
+            return mismatch("Hasher");
+        }
+
+        let mut secondary = params.secondary_hasher(seed);
+        for chunk in input.chunks(7) {
+            secondary.write(chunk);
+        }
+        if secondary.digest() != vector.secondary {
+            return mismatch("Hasher (secondary)");
+        }
+
+        let mut fingerprinter = params.fingerprinter(seed);
+        for chunk in input.chunks(100) {
+            fingerprinter.write(chunk);
+        }
+        if fingerprinter.digest() != expected {
+            return mismatch("Fingerprinter");
+        }
+    }
+
+    Ok(())
+}
+
+/// `(PARAMS index, seed, len, hash, secondary)`, computed with the
+/// reference C implementation in `umash-sys`.
+#[rustfmt::skip]
+static TABLE: [(usize, u64, usize, u64, u64); 450] = [
+    (0, 0x0, 0, 0x0a406393dec0e0d8, 0xcac20f5de451db41),
+    (0, 0x1, 1, 0x0526aba4674c5c57, 0x0c12eea8682ed1f1),
+    (0, 0x2a, 2, 0xd080428512891d27, 0x246ac7c51e515ef9),
+    (0, 0xffffffffffffffff, 3, 0xeb37ef25cb5bd9bd, 0x06b2da960ac4d9c0),
+    (0, 0x0, 4, 0xeea5077385c10fae, 0x0cc0dfcb5267966d),
+    (0, 0x1, 5, 0x0c54469e80bcf289, 0xdff7b558e6f6505a),
+    (0, 0x2a, 6, 0x30abe1055146ffa5, 0xa679a8e5eb3b07ad),
+    (0, 0xffffffffffffffff, 7, 0x7c0dd284c0628110, 0x885e060abf23e51e),
+    (0, 0x0, 8, 0xff3f7c6fdc04b9ee, 0x4862929c0d3bdc0a),
+    (0, 0x1, 9, 0x91285b72ceb3fdae, 0x59e74dd3d43adc6a),
+    (0, 0x2a, 10, 0xf813bd1700745df3, 0xc95e063295e62d38),
+    (0, 0xffffffffffffffff, 11, 0x0c29f8ecfa51b5d9, 0x08121223008bd391),
+    (0, 0x0, 12, 0x37a55ea06d54f6ca, 0x2d17e13384e0dc04),
+    (0, 0x1, 13, 0xd37810a7a75317eb, 0xc27dfd07d905b13c),
+    (0, 0x2a, 14, 0x24fbfd1b64ba674a, 0xffc33ff6be8b59b1),
+    (0, 0xffffffffffffffff, 15, 0x8e389294fa9976c8, 0xb3ef25ef3ca73ddc),
+    (0, 0x0, 16, 0x695a8593ba0c3ca8, 0xa79c470ecbfcaa4a),
+    (0, 0x1, 17, 0x28a3ec4e9a718933, 0xc1086950510ab240),
+    (0, 0x2a, 18, 0x574d97510432f319, 0xf7871d1e0d73b483),
+    (0, 0xffffffffffffffff, 19, 0xbc547c7e1a225cc2, 0x90bd6ed6de9227eb),
+    (0, 0x0, 20, 0x5e6784242f6c74e0, 0x6e619b9be3f110e3),
+    (0, 0x1, 21, 0x9203dd4772097f0c, 0x97dfd8cfcf88fb21),
+    (0, 0x2a, 22, 0x2c4035bc48de2d30, 0x881c08335e141d79),
+    (0, 0xffffffffffffffff, 23, 0x70c64504e478a63f, 0xb86cdaf926f364c3),
+    (0, 0x0, 24, 0x595ea11eb97cb1b1, 0x39cf470ed3e9fefd),
+    (0, 0x1, 25, 0x1b84d24116b9740c, 0x778312c535bb231d),
+    (0, 0x2a, 26, 0x7cea85fba45969a2, 0xe7dcd43220622aba),
+    (0, 0xffffffffffffffff, 27, 0xaeebae7ef7f423df, 0xd3649b00f331031e),
+    (0, 0x0, 28, 0x21266695fd401878, 0x4b6d4d5accb9072a),
+    (0, 0x1, 29, 0xcb750716006ec21a, 0x81d341710f692cb4),
+    (0, 0x2a, 30, 0xc206f12a11d64901, 0x98bc9f378ed72cc9),
+    (0, 0xffffffffffffffff, 31, 0xc474a40a9e8c5d4c, 0xa2a2c50ad8dd33a9),
+    (0, 0x0, 32, 0x18e56af339f7cf7e, 0x16cf43403b987b0d),
+    (0, 0x1, 33, 0x6e23cde0a07c3c62, 0x9c55d73d2aa1abf7),
+    (0, 0x2a, 34, 0xbf9c1f6e84a66073, 0x9f3922db5a7afdcf),
+    (0, 0xffffffffffffffff, 35, 0xebd06ec69836246c, 0xc353c2d9932bdfa7),
+    (0, 0x0, 36, 0xb1c5c27c40fe41ef, 0x8fc60c3bb7970ce6),
+    (0, 0x1, 37, 0x66ad7d35a2ca7060, 0xe34ab5461c4b47cb),
+    (0, 0x2a, 38, 0x931d7d38c5bccf99, 0x0d0f1c645a6e2540),
+    (0, 0xffffffffffffffff, 39, 0x59a02b4a97d321d9, 0x1340b6a8390aee68),
+    (0, 0x0, 40, 0xa016ded59acd3ab4, 0xdea8a51d06dde5cc),
+    (0, 0x1, 41, 0xb01b1ed863334f28, 0xfab19a2932149e17),
+    (0, 0x2a, 42, 0x8447533c3d8f96a0, 0x17172fe8ff34abb8),
+    (0, 0xffffffffffffffff, 43, 0x949acb11860137fa, 0xe53b29d98c0d7c4a),
+    (0, 0x0, 44, 0x769925e3a229c65f, 0xd4b83649355f8211),
+    (0, 0x1, 45, 0xd35ffd714be73c02, 0xadf25d835ffb15a7),
+    (0, 0x2a, 46, 0xd3d8ba757c200778, 0x5ab3e35b4b718eff),
+    (0, 0xffffffffffffffff, 47, 0xf77c590a1cbda3a9, 0x67dbc31cbb40b53e),
+    (0, 0x0, 48, 0x9c615a16b4d6a660, 0xb3050460423e0aec),
+    (0, 0x1, 49, 0x4052ada15dbf162d, 0xf8fc70abc950080b),
+    (0, 0x2a, 50, 0x74a9233d6904166c, 0x4e67a7c3e2be9ed3),
+    (0, 0xffffffffffffffff, 51, 0xb0a163bd0bf9aade, 0x18316eef7ea13962),
+    (0, 0x0, 52, 0x419e5efeda7fa025, 0x992aeaeaed27246c),
+    (0, 0x1, 53, 0xf5227b7fcfd232dc, 0x93d431640ad7f6ce),
+    (0, 0x2a, 54, 0x9e3cfe32f7a5ee11, 0x639da62a1525fd5a),
+    (0, 0xffffffffffffffff, 55, 0x9129296bf1d92cb4, 0x8ce18de199adeaad),
+    (0, 0x0, 56, 0x5dbb89703e3f40e1, 0xec1eec50c8e8528d),
+    (0, 0x1, 57, 0xe9d3f2c6b3b26b92, 0xae189c99c90b141b),
+    (0, 0x2a, 58, 0x444117c8d44062ba, 0xc5e7afe6f17bf6ad),
+    (0, 0xffffffffffffffff, 59, 0xfcceb6a48cbb2b7f, 0x58be8ffef3364dd8),
+    (0, 0x0, 60, 0x2b4583c9b6263af6, 0x0a86900712c6982f),
+    (0, 0x1, 61, 0xc3e227f46addc4ea, 0xf3ca29863cedb63d),
+    (0, 0x2a, 62, 0x978ac09933801fc0, 0x590fa6ef3cb1501c),
+    (0, 0xffffffffffffffff, 63, 0x506f815f2b9a31b6, 0x11e4dc25bd30a131),
+    (0, 0x0, 64, 0xd6bcfc764cd0c3a5, 0x78e5c063ed1fdc63),
+    (0, 0x1, 100, 0xf87c186be06efe61, 0xfab882e0af85ea30),
+    (0, 0x2a, 127, 0xdb224325f80df9c5, 0xc6ff8682157feb06),
+    (0, 0xffffffffffffffff, 128, 0xaeb6df82ffbeb505, 0x1bcda043d8bbf211),
+    (0, 0x0, 129, 0x50ceb1522ecb7284, 0x7fa19a28466a43c0),
+    (0, 0x1, 255, 0xbd636c9c6ed05145, 0x2db33768d118cea1),
+    (0, 0x2a, 256, 0xcc7b317438de7164, 0x8e17b682c1e73f8f),
+    (0, 0xffffffffffffffff, 257, 0x45578b92daf413d0, 0x615e683e34ef8ae0),
+    (0, 0x0, 300, 0x52f5f19f1de96e39, 0x8a5a6725f5ecbe69),
+    (0, 0x1, 511, 0x4fdef2b68c3fcb95, 0x3dda9c4ff0ba914b),
+    (0, 0x2a, 512, 0x52447dcf7f7da38b, 0x536ffcaadb95f9d9),
+    (0, 0xffffffffffffffff, 513, 0x8ff9efaa430e6524, 0x0bdcb4fa27089d15),
+    (0, 0x0, 1000, 0xed82ba23b4d20713, 0x9360b7ffe3a47732),
+    (0, 0x1, 1023, 0x9f57e62b62364bf9, 0xb848edae131bab85),
+    (0, 0x2a, 1024, 0x2156da40a079e38a, 0x47a6325a7112b204),
+    (0, 0xffffffffffffffff, 1025, 0xd4d0e965ba53f7fb, 0xece8677d807c7563),
+    (0, 0x0, 2047, 0x0f617a51b2f3b9df, 0xdc671e52d32dff2b),
+    (0, 0x1, 2048, 0x9feb1361b5cbfd17, 0xdf03598ff4893ef4),
+    (0, 0x2a, 2049, 0x18718180c6998fd6, 0xcffc1b53fd86faff),
+    (0, 0xffffffffffffffff, 4095, 0x7b4365eedf0c07b9, 0xa4cb58e73237a1f8),
+    (0, 0x0, 4096, 0xcc4d0c936721cea9, 0x5ee5123d711f8887),
+    (0, 0x1, 4097, 0xc070d795c66ca77d, 0xfa4763e9615f16be),
+    (0, 0x2a, 5000, 0x68b95c2ec2302b3f, 0xdd75f727046d5791),
+    (0, 0xffffffffffffffff, 8191, 0x8fa8226f6cffd0af, 0x66f2dff389638abb),
+    (0, 0x0, 8192, 0x00e87ed9864d3283, 0x11fee9504c089787),
+    (0, 0x1, 8193, 0x1cfebac5bebd5a31, 0x5f931611d1fbff0a),
+    (1, 0x1, 0, 0xfbbcbe61daa356e6, 0x3f7aa83811ea1c64),
+    (1, 0x2a, 1, 0xac394ec9a3865eaf, 0xfdac68502fdc6365),
+    (1, 0xffffffffffffffff, 2, 0x13ebf42b7b3cd423, 0x4fcd3c40027dc008),
+    (1, 0x0, 3, 0xa02ac4f6d817e838, 0xcb7971b3725ad990),
+    (1, 0x1, 4, 0xc6095f416ec78d02, 0xe61c819fe3393d39),
+    (1, 0x2a, 5, 0xa7a16cda8edb45b8, 0x89f7a7623fb62234),
+    (1, 0xffffffffffffffff, 6, 0xf53581890fa29d54, 0x7efb09bd11d24f8a),
+    (1, 0x0, 7, 0x6609a24bbe214de7, 0x61a2562efa22c7b8),
+    (1, 0x1, 8, 0xbe97698fcd5151e5, 0x9058e803e621f0d4),
+    (1, 0x2a, 9, 0x25565ed4d4033eec, 0x4ce561278f144c04),
+    (1, 0xffffffffffffffff, 10, 0x11b231cd40aeec70, 0xc27a785c1759ee96),
+    (1, 0x0, 11, 0xbc83a1e7a3b3896a, 0x1a2710a9a8bb3c66),
+    (1, 0x1, 12, 0x36f782f41342d060, 0xd86674c53c286f30),
+    (1, 0x2a, 13, 0xda9cab1ff5a5a797, 0xb5ba94d839db636f),
+    (1, 0xffffffffffffffff, 14, 0xf0a96a1eaa16a2c4, 0x85f4523d5175a4ca),
+    (1, 0x0, 15, 0xcbe5bd51175f92a4, 0x329b024088ae46bd),
+    (1, 0x1, 16, 0x2ed078a131e51199, 0xe25fbd09cfa406f9),
+    (1, 0x2a, 17, 0x4dc81d00a6a7ea99, 0x73ea506e7152d356),
+    (1, 0xffffffffffffffff, 18, 0x1281fc6076edcb1a, 0x50e4eda1c0130747),
+    (1, 0x0, 19, 0x98c545441597ae6f, 0x73d063f21ee75221),
+    (1, 0x1, 20, 0xffc4bf9176b2b664, 0xaea46dc72624554d),
+    (1, 0x2a, 21, 0xeda58efd01ca2094, 0xd12c2d91f2d1de57),
+    (1, 0xffffffffffffffff, 22, 0x4aab47269b1b6113, 0x354cd79275b572bd),
+    (1, 0x0, 23, 0xb477cb8f2311ac5d, 0x7ed02fca5a20fc34),
+    (1, 0x1, 24, 0xd81a6cb89de06585, 0x18b9eade5a1c836c),
+    (1, 0x2a, 25, 0xda469475b5304d03, 0x9b0cb0327a1817c2),
+    (1, 0xffffffffffffffff, 26, 0xf57d68192412d820, 0x1fd1bd3d88f0a55a),
+    (1, 0x0, 27, 0x3dbd045f6efa623e, 0xdb70b1d31f79f330),
+    (1, 0x1, 28, 0x25a84291f98e2a8f, 0x79705036f0ba0eae),
+    (1, 0x2a, 29, 0xd2444ae024d8bd50, 0x86201c53a26f9051),
+    (1, 0xffffffffffffffff, 30, 0x0957743d691981fc, 0x1aad6bdb09acb88b),
+    (1, 0x0, 31, 0x5195cb3fe8947d78, 0x6d8356cccb25e825),
+    (1, 0x1, 32, 0xa3e9fe7931399385, 0xfdac9ce1413ad202),
+    (1, 0x2a, 33, 0xa763527fd72c25d6, 0x828f64a0a0979684),
+    (1, 0xffffffffffffffff, 34, 0xe6fe61df537b3370, 0x4c2b32ef994258be),
+    (1, 0x0, 35, 0x2bc2f873fbc51c9d, 0xce84ca3e00f6215c),
+    (1, 0x1, 36, 0xca9b56a603824ec4, 0x9c0fa97b9d926d89),
+    (1, 0x2a, 37, 0xd5c0207cde6ff363, 0xa5b2f60cbaa1613e),
+    (1, 0xffffffffffffffff, 38, 0xe15696a82c41b841, 0x1cb00bfabd854760),
+    (1, 0x0, 39, 0xdc3b52cdfbdee3e2, 0x93dd75c58336c3df),
+    (1, 0x1, 40, 0xdb59b145c5d7f2c0, 0x72e693a89934a174),
+    (1, 0x2a, 41, 0x7c688063e4a5d1e0, 0x258facb6fd7b4cb8),
+    (1, 0xffffffffffffffff, 42, 0x13d391ade45024d1, 0xd2d481ae2639f5a4),
+    (1, 0x0, 43, 0x11056dfb697bedbe, 0x0b7d7f0c14167f6e),
+    (1, 0x1, 44, 0xdcae644102d23d91, 0x44e6ebe977129b91),
+    (1, 0x2a, 45, 0xb2a38f6797506695, 0x5c38a77fa2400a97),
+    (1, 0xffffffffffffffff, 46, 0x29a1896608722c64, 0x93b5a268cc76edd4),
+    (1, 0x0, 47, 0xb86b840c5d86b8f4, 0xe313141cd83940a4),
+    (1, 0x1, 48, 0x20c37d496a2d7f0e, 0xfee2549c6971c400),
+    (1, 0x2a, 49, 0x1b2f388a85fc6115, 0xad12777eef586722),
+    (1, 0xffffffffffffffff, 50, 0xd720bbb7ffdad36b, 0x97b779cfa15c5a88),
+    (1, 0x0, 51, 0xa6442727340de220, 0x12e29d0e39f8b51c),
+    (1, 0x1, 52, 0x21f47270ae242a5b, 0xa6bd4b90b28b61eb),
+    (1, 0x2a, 53, 0xd247e629c5b0ab16, 0xdc75a69f90b069c2),
+    (1, 0xffffffffffffffff, 54, 0xf1ce6753687ef2c9, 0xafe5fbf675bb5349),
+    (1, 0x0, 55, 0x9c6e1eac32201c27, 0x7ea96fef47d96743),
+    (1, 0x1, 56, 0x5a44736d3fc683d2, 0xf2a5a9c4610245e7),
+    (1, 0x2a, 57, 0x5d2dfbfa780de6b0, 0x60b12af7e732c809),
+    (1, 0xffffffffffffffff, 58, 0x9465b571e3131190, 0x9f5698a843c64e62),
+    (1, 0x0, 59, 0xaae00bbd45d083c1, 0x1b8164bf6094a3f6),
+    (1, 0x1, 60, 0xddff71366218a89d, 0x0d174b79a3dce28d),
+    (1, 0x2a, 61, 0x583e42e512e3908e, 0x7a83a189766df514),
+    (1, 0xffffffffffffffff, 62, 0xd4f687e7c551cf6d, 0xe75b6a5e5cd75eb9),
+    (1, 0x0, 63, 0xae07544c1553a574, 0x75b7cf26899b7d48),
+    (1, 0x1, 64, 0x972d4cacb507e847, 0x4b61b16b4fba00cc),
+    (1, 0x2a, 100, 0x98337ac4c3dd4ec0, 0xed3c5eb723db956b),
+    (1, 0xffffffffffffffff, 127, 0x80aa64d2e3c6a308, 0x969c432a514c8af1),
+    (1, 0x0, 128, 0xb32a078b1fe22524, 0x0f30666c7d783350),
+    (1, 0x1, 129, 0x05149cfd9118af5e, 0xcc159c5e38e81666),
+    (1, 0x2a, 255, 0x575021652d7946c1, 0xe8428c63f4c384c0),
+    (1, 0xffffffffffffffff, 256, 0x5df83dbacc00d55d, 0x59c4d52d7965d89a),
+    (1, 0x0, 257, 0x19e6abbcc06a198b, 0xeab5ba42c90c7270),
+    (1, 0x1, 300, 0x7d8c467e285d52b4, 0xdb67ba01529f6438),
+    (1, 0x2a, 511, 0x77bdc12b925e43c2, 0x815e94ae548bedf8),
+    (1, 0xffffffffffffffff, 512, 0x26a0659448bfe8dc, 0xce7de84c91ce2d90),
+    (1, 0x0, 513, 0x40c26864bede0850, 0x485cad3a6b538bda),
+    (1, 0x1, 1000, 0x44dc7e55d01efc8f, 0x3271724613b49523),
+    (1, 0x2a, 1023, 0xfab84f927180b378, 0xf926e62845908a65),
+    (1, 0xffffffffffffffff, 1024, 0xc1fa36c565338bf7, 0xf48845d61e68ba59),
+    (1, 0x0, 1025, 0xb83e257c25ed1b60, 0x142f3e0d67015bb0),
+    (1, 0x1, 2047, 0xaddef053f87e774a, 0x9cea82a8957f3615),
+    (1, 0x2a, 2048, 0xf28680c407f8c131, 0xe54d4b4de0bfe500),
+    (1, 0xffffffffffffffff, 2049, 0xed279efdf18e1c45, 0x1cae3150eedf2327),
+    (1, 0x0, 4095, 0xedc1f26615ecf21f, 0x95e8fbe84abb9e47),
+    (1, 0x1, 4096, 0x58de4b4f00c77659, 0x7270493e981af860),
+    (1, 0x2a, 4097, 0x32fbbc4ce3420c7a, 0xa30e423bff68c267),
+    (1, 0xffffffffffffffff, 5000, 0xd5d2ef5f216bd530, 0x577b9b029680229b),
+    (1, 0x0, 8191, 0x41ba6ae142c72e04, 0xf5a840b61bcc9e04),
+    (1, 0x1, 8192, 0xa88c116147b20a6b, 0x8b328915d868d0b0),
+    (1, 0x2a, 8193, 0xc2db3fdebab20bdf, 0x9d41ae93dbf0810e),
+    (2, 0x2a, 0, 0x6c0b659fdb9016e4, 0x50db74962d248fce),
+    (2, 0xffffffffffffffff, 1, 0x98e4412fb0ec5c66, 0x0e3357b224a9e378),
+    (2, 0x0, 2, 0xd96acb4913d20b30, 0x36fbfe2a4ac13d07),
+    (2, 0x1, 3, 0x47f7b5b1147f0931, 0xe90614973cedd010),
+    (2, 0x2a, 4, 0x8c9463ce6b46ab2a, 0x0aac5083c3991b77),
+    (2, 0xffffffffffffffff, 5, 0x1a8d84fb79f40a4f, 0xdbfb8334323406c5),
+    (2, 0x0, 6, 0x0c20ce0c3c025497, 0x24f274de5f8dd3e4),
+    (2, 0x1, 7, 0x8771b7b187e99408, 0x6ff4f1997bff10c3),
+    (2, 0x2a, 8, 0x8ff21dcb03dbc1a9, 0x203e097488a01ea9),
+    (2, 0xffffffffffffffff, 9, 0x81d215095e5e7383, 0x6c95c6dd9baa2a4c),
+    (2, 0x0, 10, 0x8587223affb6a036, 0xf391e15d1b07f317),
+    (2, 0x1, 11, 0x808734e181f63c8b, 0xac7461b9277a9ccb),
+    (2, 0x2a, 12, 0x88a6e4d6f8297f99, 0x120ff1371ab416af),
+    (2, 0xffffffffffffffff, 13, 0x30901b0eb599ac4d, 0x2a08122009ab4347),
+    (2, 0x0, 14, 0xce888cd2450351a6, 0xfd5f647ad52a62e7),
+    (2, 0x1, 15, 0xef12180a1f3d7245, 0xd871027bcc7c191c),
+    (2, 0x2a, 16, 0x93bbacbdf5a87e72, 0x7253d0bbf9ad89f5),
+    (2, 0xffffffffffffffff, 17, 0x0b40420a2ea145d2, 0x37dd5863023865f7),
+    (2, 0x0, 18, 0x1dda88cd404729ac, 0xdc4c10abe9a1d804),
+    (2, 0x1, 19, 0xa73e30db00cdac72, 0x1f5a677d05378c77),
+    (2, 0x2a, 20, 0x4bbd01914e6cef57, 0x80c770face0aa2c2),
+    (2, 0xffffffffffffffff, 21, 0x7aa515d3df181ed9, 0x09d77ec689eedc94),
+    (2, 0x0, 22, 0x1837e9353c3043a7, 0x3f62a5a1da820c5b),
+    (2, 0x1, 23, 0xc3a7ee2cab221cd7, 0x50300da3b98237f6),
+    (2, 0x2a, 24, 0xc92e29d16e62c65c, 0xe8cb20f730aec8e8),
+    (2, 0xffffffffffffffff, 25, 0x69e5d44f45381175, 0x0fcdbcd9a04a12b9),
+    (2, 0x0, 26, 0xf832e295397b0d1c, 0x6ae5830aeb6d7094),
+    (2, 0x1, 27, 0x77dfd0df3b0b45f2, 0x4527ab54acdd82d5),
+    (2, 0x2a, 28, 0x3a7281183add29c7, 0x88f5d320109738db),
+    (2, 0xffffffffffffffff, 29, 0x883febed5d0154bd, 0xdf669a0f5d557bfa),
+    (2, 0x0, 30, 0x595c77c33e0540c2, 0xe347329d834676d3),
+    (2, 0x1, 31, 0xe6de441d754c4a80, 0x05557bcbdbbc504f),
+    (2, 0x2a, 32, 0x39b40c6a47263f8f, 0xb278311f4d6c3048),
+    (2, 0xffffffffffffffff, 33, 0x869b63e9203b0fc0, 0xf618b7b4e59239ae),
+    (2, 0x0, 34, 0xe52d2646077372ec, 0x898a3ad663e6f3d6),
+    (2, 0x1, 35, 0xa943d6474fba3fb8, 0x94e196e9ae6ed84a),
+    (2, 0x2a, 36, 0x038ed82b52e517da, 0x98628a3ec22db49d),
+    (2, 0xffffffffffffffff, 37, 0xbd7f7d9dfd29402f, 0x6df84ed0b73dd032),
+    (2, 0x0, 38, 0x157b16a9ec542061, 0x023a644c9cb45957),
+    (2, 0x1, 39, 0xcd0b1442f6d6ab7c, 0xa30764d9d47f4d37),
+    (2, 0x2a, 40, 0x856574bf0e863da3, 0x4329b3cc22f68951),
+    (2, 0xffffffffffffffff, 41, 0x9d27e5c718815251, 0x7900b2d843548e90),
+    (2, 0x0, 42, 0x35a6c5c03c1b7b92, 0x56a946a5792f0a77),
+    (2, 0x1, 43, 0xe8c76f638745d2cb, 0x353e791b5809b253),
+    (2, 0x2a, 44, 0xd42684beebe069df, 0x892b231903628c5d),
+    (2, 0xffffffffffffffff, 45, 0x951c51f47d3e1452, 0x9405ca47d3f42b10),
+    (2, 0x0, 46, 0xa87c373b8ec7b560, 0x13f4accf271f5a08),
+    (2, 0x1, 47, 0xf4786c65c7842ce8, 0xd99b677ae26b2bb5),
+    (2, 0x2a, 48, 0xd544cfbde7b524df, 0xf70915c8b9516137),
+    (2, 0xffffffffffffffff, 49, 0xb27f3236f10741cc, 0x7a58a3ee4c6d19db),
+    (2, 0x0, 50, 0xfa638eba03ddac9f, 0xd739d898ad5419f3),
+    (2, 0x1, 51, 0xb60f077ecef7dfa2, 0xdd175fa5fe984011),
+    (2, 0x2a, 52, 0xec8f02f8fec47e81, 0xc9b1be23fd7125bd),
+    (2, 0xffffffffffffffff, 53, 0x558b7c7b9edecbdc, 0xf2046d32b44512c5),
+    (2, 0x0, 54, 0x558a98eb910bef49, 0x59569edf0e008062),
+    (2, 0x1, 55, 0xd5afea97da07d3b5, 0x445c38b2eead8e1a),
+    (2, 0x2a, 56, 0xab5217229dd1df22, 0x7a24cc50beb72b3f),
+    (2, 0xffffffffffffffff, 57, 0x3430cce4cc1d514c, 0xc39729d18230e5c7),
+    (2, 0x0, 58, 0x0ee151136c586040, 0x74934a74fa621922),
+    (2, 0x1, 59, 0x0859885c8853ec0c, 0xf6ba9b4b7113cdac),
+    (2, 0x2a, 60, 0xa77813ac53bad9b8, 0x51b299d12bbd3eb9),
+    (2, 0xffffffffffffffff, 61, 0xcaa5087aa4775abf, 0xccdc3261f33c2931),
+    (2, 0x0, 62, 0xfb19938668c7b460, 0x222723ad524919dd),
+    (2, 0x1, 63, 0x4b082415f9f2399e, 0xb78c901b4fbf1ddc),
+    (2, 0x2a, 64, 0x6f159608bf5a970d, 0xfe34b64afefc4363),
+    (2, 0xffffffffffffffff, 100, 0x46a41956c4af8ece, 0xeb83aaccbddb9b97),
+    (2, 0x0, 127, 0xf6ced9d9e6e61339, 0xb08d4db66dbe84b6),
+    (2, 0x1, 128, 0xfa07286fabf28294, 0x948df49529af5d5b),
+    (2, 0x2a, 129, 0x47931b736f7db955, 0x165dd23fb370d9e4),
+    (2, 0xffffffffffffffff, 255, 0xa480d5d30d8e87f2, 0xf66790283826d3c7),
+    (2, 0x0, 256, 0x2e9ea20f5571e88e, 0x74f4b77125c48834),
+    (2, 0x1, 257, 0x5f3473deeff818ca, 0x6f2f197000558d4e),
+    (2, 0x2a, 300, 0x672f20e367049e0e, 0x48f075b7573c6a35),
+    (2, 0xffffffffffffffff, 511, 0x862ed2ae0329f8ad, 0xfe47d68ad692f963),
+    (2, 0x0, 512, 0xaa60d0be5cdf345d, 0x610827be8875d0af),
+    (2, 0x1, 513, 0xc65430321dc8e1ef, 0x31f4faf539c79aaf),
+    (2, 0x2a, 1000, 0x9be1e66a1269093a, 0x5138238d72738329),
+    (2, 0xffffffffffffffff, 1023, 0x58a3962f35b55016, 0x3d804c83d967c908),
+    (2, 0x0, 1024, 0x175b525df622ea9c, 0x3ece92fb14e580aa),
+    (2, 0x1, 1025, 0x204bfa521b38fbd2, 0x7640a601f65e6dbd),
+    (2, 0x2a, 2047, 0xdf2fdb04a4064a08, 0x760d86f187d58555),
+    (2, 0xffffffffffffffff, 2048, 0xe3c352ba7fe9c5d6, 0x7cc6bd229238c7a2),
+    (2, 0x0, 2049, 0x1a147c25743adf27, 0xe0b54473e7132594),
+    (2, 0x1, 4095, 0xc03803655756db48, 0x62c392529fdcbd06),
+    (2, 0x2a, 4096, 0xdaeb987a5ffeb4bf, 0xd30189e38f9abfee),
+    (2, 0xffffffffffffffff, 4097, 0xc06ce72c01b29d7f, 0x77966d548507b9b0),
+    (2, 0x0, 5000, 0xb49729442551bc6f, 0x67219834576fd1f8),
+    (2, 0x1, 8191, 0x7ee129e798e6a7a1, 0x938892ac7fb1f0e2),
+    (2, 0x2a, 8192, 0x62de1e0406d787cc, 0xbdf7953e3c44f1b5),
+    (2, 0xffffffffffffffff, 8193, 0xd7c1b84d6e6d6287, 0x3089b36e46284265),
+    (3, 0xffffffffffffffff, 0, 0x157ce291cce9555a, 0x61ff2f847168d0d4),
+    (3, 0x0, 1, 0x0c86660218196c69, 0xeeb0a2b3bbb5973a),
+    (3, 0x1, 2, 0x6eafc0025abd43b4, 0x02cccae08fb87adb),
+    (3, 0x2a, 3, 0xd687d4776b998658, 0x736d719f06c11474),
+    (3, 0xffffffffffffffff, 4, 0xf1d431badbdd7bc0, 0x25c241f1f6e03625),
+    (3, 0x0, 5, 0xfaaabf65d395f93f, 0xf8db54c5691186a5),
+    (3, 0x1, 6, 0x77e17c623ce1bb1b, 0xca0edd23d11c7a58),
+    (3, 0x2a, 7, 0x6a95f0020e941eca, 0xd3e9f75f49fcffce),
+    (3, 0xffffffffffffffff, 8, 0x8b92c8456ddabd6f, 0xfc00d0805d179383),
+    (3, 0x0, 9, 0x5cf5f665a805607d, 0xa5c7b2910d03b0d3),
+    (3, 0x1, 10, 0x89c763945b16c8b4, 0xa63af4d9f0e9bc4a),
+    (3, 0x2a, 11, 0x7ddfd75b5246f23a, 0xe03acc07c26c7351),
+    (3, 0xffffffffffffffff, 12, 0xe048f837835f7389, 0x0e263f4a47a42f2e),
+    (3, 0x0, 13, 0xc2dc19e3b73e71a7, 0xa35eb15bc3232195),
+    (3, 0x1, 14, 0xcaca6760678a4544, 0x3d15602b4fb4e458),
+    (3, 0x2a, 15, 0xe073de344c897dcd, 0x12ea65c38884f0f6),
+    (3, 0xffffffffffffffff, 16, 0x7e37319827171abf, 0x4c903644e38df4af),
+    (3, 0x0, 17, 0xcdd0bab6f0d35e58, 0x45966e90c0ed838b),
+    (3, 0x1, 18, 0x33407fcbc1cca228, 0x22f907cf09ef3dc5),
+    (3, 0x2a, 19, 0x0437189a85acdf57, 0xed9ffc9d267c4350),
+    (3, 0xffffffffffffffff, 20, 0x5483e648596f60e1, 0x8bcb66d7efa756bf),
+    (3, 0x0, 21, 0x34d1794b2af17a70, 0x7b08261d179ea914),
+    (3, 0x1, 22, 0xe58a2131d8dfbc3b, 0xcdc066a20abb9bc8),
+    (3, 0x2a, 23, 0x50e8bc61bbfda8f0, 0xe615df09fc3b3fb0),
+    (3, 0xffffffffffffffff, 24, 0x38904b69cb3e3c1d, 0x97421d68ef2e147a),
+    (3, 0x0, 25, 0x7c17b13672c71cd3, 0x566f4a8e5dc21e59),
+    (3, 0x1, 26, 0x8c63dfa2ad67d693, 0x31cd8c02fb3945a3),
+    (3, 0x2a, 27, 0x6ca4a21c8cd034ab, 0x0fda755631b438c3),
+    (3, 0xffffffffffffffff, 28, 0x73e351a0470aeb24, 0x8c0f0d553d680faf),
+    (3, 0x0, 29, 0x718dbf9d10bc6d97, 0x4a6e9384c1529158),
+    (3, 0x1, 30, 0x8a1f6a139ee1aa83, 0x760be91a6433ec87),
+    (3, 0x2a, 31, 0x1959c3bd75fcf92b, 0xce73999f577442e4),
+    (3, 0xffffffffffffffff, 32, 0x4c4983cc6f38d56b, 0x2ca9c8bc35bf7054),
+    (3, 0x0, 33, 0xd3f0df15998e7c33, 0x7474b057abcf53af),
+    (3, 0x1, 34, 0xa3b08f58139ba1c5, 0x5c1d14506163170e),
+    (3, 0x2a, 35, 0x90324b112b162d09, 0xf18f11dd9863fcd0),
+    (3, 0xffffffffffffffff, 36, 0x0e9f96d107dd5375, 0x63ee722360e98ae3),
+    (3, 0x0, 37, 0x8afb9e1fee01e2ca, 0x4f862b43096c9207),
+    (3, 0x1, 38, 0x6ed187f44327e5c3, 0xb8a36a5f961be727),
+    (3, 0x2a, 39, 0x9feb4f7b1f057fc9, 0x378b7645b5a06186),
+    (3, 0xffffffffffffffff, 40, 0x4d99ed9f2e287b83, 0x8f70b9e19ad6c1f0),
+    (3, 0x0, 41, 0x43fc0363bb414f07, 0x3da8f13981e612fb),
+    (3, 0x1, 42, 0x0dd39763635dff24, 0xf19fa58c6b188a60),
+    (3, 0x2a, 43, 0x56f82ff97b4a107e, 0x5825e9dcb32f2173),
+    (3, 0xffffffffffffffff, 44, 0xf862f64d32b6b084, 0x21da8c3f5f576413),
+    (3, 0x0, 45, 0x6f0042e54e611993, 0x2542ae14c36a3456),
+    (3, 0x1, 46, 0x577d38b51cdd5358, 0x766eed006ce4b76b),
+    (3, 0x2a, 47, 0x12585ef6774af377, 0x334cef0eb979b656),
+    (3, 0xffffffffffffffff, 48, 0xa5136cc5f8d7f6ed, 0xf1d72c2b44253a51),
+    (3, 0x0, 49, 0x0ea1dee059910cf6, 0x8c5c9467226606ec),
+    (3, 0x1, 50, 0xefdd9c929b5c5440, 0x774685cc2b97f379),
+    (3, 0x2a, 51, 0x21881ff6c9ddefe3, 0x7d47f44bdad50597),
+    (3, 0xffffffffffffffff, 52, 0x8eed5a26a78d98be, 0x2b06c98b4774feb3),
+    (3, 0x0, 53, 0x080c856cddfe1eae, 0x5142e7ed34626961),
+    (3, 0x1, 54, 0xdab923b03b583d6f, 0x0187a7619c38fe9a),
+    (3, 0x2a, 55, 0x0cc54f96d49f0de1, 0xff8e79727840243c),
+    (3, 0xffffffffffffffff, 56, 0x7bacb32fd9b20391, 0xd07ea1ef5c737ae1),
+    (3, 0x0, 57, 0x5f247a5eb4f37c33, 0xfd10d3bcdda0325c),
+    (3, 0x1, 58, 0xfe722f16b4495d8f, 0x2e36a0590a905d3f),
+    (3, 0x2a, 59, 0x0d9ef53bb80077e6, 0xf5ea8816e8a0b3f2),
+    (3, 0xffffffffffffffff, 60, 0x4e7770e3f39b57ac, 0xa5c9cbd5af62bc32),
+    (3, 0x0, 61, 0x3dfff64a13dd8c8d, 0xa00c5f5f43338ec5),
+    (3, 0x1, 62, 0x33b915a1839dcdd2, 0xa482230e7aa7c433),
+    (3, 0x2a, 63, 0x931528c8c9a9cb62, 0x81d7443e961bab46),
+    (3, 0xffffffffffffffff, 64, 0x0b7dd9454d9f0cb5, 0x5bb7060f733d2e7c),
+    (3, 0x0, 100, 0x3e5d3c7f28b373a8, 0x52318461d0f3e0ed),
+    (3, 0x1, 127, 0xe00ac38eb2518445, 0xc38c8b2ff42eec17),
+    (3, 0x2a, 128, 0xa6b0671d8ff2eba3, 0x4702241e73468eaa),
+    (3, 0xffffffffffffffff, 129, 0x5f71a927a0e066ac, 0x5b7fc11e82280d39),
+    (3, 0x0, 255, 0x3846c6c4e61dba96, 0xb31049721286bc5e),
+    (3, 0x1, 256, 0x6fc7e90e412e578a, 0x4afeedd9c1ef9400),
+    (3, 0x2a, 257, 0x46c95e9bd0d18230, 0x5e6bfd10094bed07),
+    (3, 0xffffffffffffffff, 300, 0x6576be3fee47f509, 0xfd9c9d4c77c3c8eb),
+    (3, 0x0, 511, 0x7cad6f54cec2ca29, 0xcec01eb32816994d),
+    (3, 0x1, 512, 0x4f6dec97b1149eeb, 0x41232560f9375b14),
+    (3, 0x2a, 513, 0xc6a0b2c3e8728a13, 0x102131f6e77d13df),
+    (3, 0xffffffffffffffff, 1000, 0xa97102da5e4d50b4, 0x80d619b86ef957c2),
+    (3, 0x0, 1023, 0xf0cb16eee867bec7, 0x15a238a0b2f52474),
+    (3, 0x1, 1024, 0xb511725209328969, 0xf14345472defb4ec),
+    (3, 0x2a, 1025, 0x03e20fc157d71bfb, 0xff96457bf3a748bb),
+    (3, 0xffffffffffffffff, 2047, 0xdd618d421c2d81a7, 0x71c38b5b3ca70466),
+    (3, 0x0, 2048, 0x23881e4a0745b7bb, 0x3945ea01350880c1),
+    (3, 0x1, 2049, 0x93d908e20c403442, 0x5a34eea234c3ab79),
+    (3, 0x2a, 4095, 0xaf1e11d4c9a5ce2d, 0xf974a0decc649c4a),
+    (3, 0xffffffffffffffff, 4096, 0xd104e4feb2c1369a, 0x77ca7dda3680e034),
+    (3, 0x0, 4097, 0xa5c15289a981cfdf, 0x9710be8dff514053),
+    (3, 0x1, 5000, 0x0a1b50aad89b368a, 0xe2f0524f727e26da),
+    (3, 0x2a, 8191, 0x4ea096424b30294a, 0x0240dcb3f07cb1f3),
+    (3, 0xffffffffffffffff, 8192, 0x870443fb69763ef6, 0xfdaa432190069f38),
+    (3, 0x0, 8193, 0xd73686f08ad54f29, 0x83ce05fd1c795f16),
+    (4, 0x0, 0, 0xd08ac7050d59119a, 0x83d5ceab37f6dbb8),
+    (4, 0x1, 1, 0x512c1ce25b7ccd77, 0xab47aac61700046a),
+    (4, 0x2a, 2, 0x6fc21c3bd8e6fc36, 0xd12120804270e64c),
+    (4, 0xffffffffffffffff, 3, 0x0b3cff7802604126, 0xc72199a02a86a08f),
+    (4, 0x0, 4, 0x3fe2ef1531fbb3d1, 0xa3ee62434a7a11b4),
+    (4, 0x1, 5, 0x477412c83288c7e0, 0x158d2c57ad716f09),
+    (4, 0x2a, 6, 0x59fdd3f5f5f3c779, 0xcd79819121155662),
+    (4, 0xffffffffffffffff, 7, 0xdb85d6f983dca608, 0x889e54ad77fde847),
+    (4, 0x0, 8, 0xd105e8d4a45ec557, 0x07a15a83765500d8),
+    (4, 0x1, 9, 0x6d38d0aa2769b12c, 0xf7b1fcc91f94736b),
+    (4, 0x2a, 10, 0x8de67c52898b622f, 0x82d530f9fc6f184e),
+    (4, 0xffffffffffffffff, 11, 0xbb89a836fe1ac996, 0x827dc48f026e1d4a),
+    (4, 0x0, 12, 0xc346e6012b9c86a0, 0x5f484b9d5ad14c44),
+    (4, 0x1, 13, 0x98d742effbf053de, 0x0f4a2af0fb7ade6d),
+    (4, 0x2a, 14, 0x3d0a0884a35e9837, 0xc0ee295389a6a58c),
+    (4, 0xffffffffffffffff, 15, 0x3f86f034a825409b, 0x92641a269dbc4b92),
+    (4, 0x0, 16, 0x65e0522b06317f63, 0xa7c3b28b5a93cbe5),
+    (4, 0x1, 17, 0x9c87cc81d2897b3c, 0xb8594a48d34c62bd),
+    (4, 0x2a, 18, 0xb4f619b839f03bf6, 0xb8058feb79839a6e),
+    (4, 0xffffffffffffffff, 19, 0xd3d7c83d14bb450d, 0x45a50293330dd052),
+    (4, 0x0, 20, 0x4bffc24d34e8f788, 0x63e43865fe5cca9c),
+    (4, 0x1, 21, 0x5e205d42ca9bf547, 0x39f9e571ddf891b1),
+    (4, 0x2a, 22, 0x641e25b3bfe2a952, 0x50d75c2879f3a7af),
+    (4, 0xffffffffffffffff, 23, 0x01af71c67b5ce30e, 0x40d6f1855be0c7cf),
+    (4, 0x0, 24, 0x0fa120a1966e7b88, 0xd1e9cdbba9e5ce52),
+    (4, 0x1, 25, 0xe8577d344e563e3c, 0x83ba9fe47c947c36),
+    (4, 0x2a, 26, 0x3de048dcccc0320e, 0x2e4b885863943f3a),
+    (4, 0xffffffffffffffff, 27, 0x99b208c893c55ad0, 0xf588fa96f5df03e2),
+    (4, 0x0, 28, 0x03b28b604420aedb, 0x998224a8fab87997),
+    (4, 0x1, 29, 0xdd5077e25676730f, 0xf525fbf35ee09fe4),
+    (4, 0x2a, 30, 0xd2e29d72178c6abc, 0x56e794e0112b55de),
+    (4, 0xffffffffffffffff, 31, 0x948784a599a21b1b, 0x19f136d8e45d320f),
+    (4, 0x0, 32, 0x743e209ecb29bd44, 0x140cc90ca029947c),
+    (4, 0x1, 33, 0x8ce2133cbcb78a34, 0x913214444d7fb418),
+    (4, 0x2a, 34, 0xf1fcd05d32ed5f8d, 0xb2cc09d8e0bf71b4),
+    (4, 0xffffffffffffffff, 35, 0x7007d88be9c0cb82, 0xe1b25e0fd9701d74),
+    (4, 0x0, 36, 0x77bc2f0d6e54ad2a, 0xdb14891563e5e418),
+    (4, 0x1, 37, 0x668e29e7cdc49465, 0x64f5c533e5bca31d),
+    (4, 0x2a, 38, 0x4d0bf40c96ddad34, 0xbe62be743452a1a3),
+    (4, 0xffffffffffffffff, 39, 0x3023282bf9c74407, 0x5bf537cd39b8547f),
+    (4, 0x0, 40, 0x1ca6c9143e94355d, 0x8fe3bb4ab75ef841),
+    (4, 0x1, 41, 0x9e519e42008c06d2, 0xeb155619a4a18871),
+    (4, 0x2a, 42, 0x4f5769cf2e8a10a3, 0xb172f6897c3e9151),
+    (4, 0xffffffffffffffff, 43, 0xd12247d8b103cb0b, 0x720832adbdb98919),
+    (4, 0x0, 44, 0xf9471aba73eef240, 0x498dcb2766e32299),
+    (4, 0x1, 45, 0x9360eff9384ead4b, 0x3ead7423701b12ab),
+    (4, 0x2a, 46, 0xe4d0997f259a6f94, 0x80358ca8c97a4ab9),
+    (4, 0xffffffffffffffff, 47, 0x39b3ecaae88d4448, 0x32d141068595f2be),
+    (4, 0x0, 48, 0xa7a27e5055f68f9c, 0x26c5a91eb54df5fb),
+    (4, 0x1, 49, 0xf0ceb76439d25462, 0x9fff1f8ee7546199),
+    (4, 0x2a, 50, 0xad05f933c859abe9, 0x05ba2c62f6431e59),
+    (4, 0xffffffffffffffff, 51, 0x04de409529a4d5fd, 0x54c94e8e678027b3),
+    (4, 0x0, 52, 0xbf5199f2623abd5a, 0x260e9038154621e9),
+    (4, 0x1, 53, 0xf8f178a3085beff3, 0x918a8a586ed384a1),
+    (4, 0x2a, 54, 0x8cf07016cf48b25a, 0x103c7b92f148442f),
+    (4, 0xffffffffffffffff, 55, 0x50367006d95f6ecd, 0x69a6a6dd8ad7469a),
+    (4, 0x0, 56, 0x446a2fed1f188c7a, 0x05336cbc868f7056),
+    (4, 0x1, 57, 0x4e11a548bea117de, 0xeedbf0aa288cc5ac),
+    (4, 0x2a, 58, 0x0b8d163f7e849d69, 0x8a6f95ad8b358cb4),
+    (4, 0xffffffffffffffff, 59, 0x082cb20d2863ae46, 0x06abdcb24484e3fe),
+    (4, 0x0, 60, 0x32bdeef8e1b56e6c, 0x9741a914f227d048),
+    (4, 0x1, 61, 0x83abac6652f8b08a, 0x70d1d3bcd60cf111),
+    (4, 0x2a, 62, 0x9c88c375ee0ecf02, 0x1d34559fc2241b43),
+    (4, 0xffffffffffffffff, 63, 0x04c92960c2c81622, 0x171e94a69cb0cc18),
+    (4, 0x0, 64, 0x999f6456714359ce, 0xf3344a17656029a0),
+    (4, 0x1, 100, 0x76af1ff0a903cee8, 0x16a5e3ef5e35c627),
+    (4, 0x2a, 127, 0x44a78f2b6eea9a14, 0xb746e13957ceb092),
+    (4, 0xffffffffffffffff, 128, 0x2f819e436f5dc3aa, 0x2d84b96513198747),
+    (4, 0x0, 129, 0xfcbffd69da4e54ed, 0x033b49ac7a09f671),
+    (4, 0x1, 255, 0x8e966fba712dc371, 0x0452f39f3a11168a),
+    (4, 0x2a, 256, 0xd1fc380126df2ac8, 0x707e2f8e5be138c1),
+    (4, 0xffffffffffffffff, 257, 0x85cb5f91c8dece1f, 0x9664cd31e0d9e32f),
+    (4, 0x0, 300, 0x38d0457041ebe6cd, 0xc819fee4c6ff62ee),
+    (4, 0x1, 511, 0x61a4c29261f79b31, 0xaa8ab87ef9c0d444),
+    (4, 0x2a, 512, 0x331d7591f8987020, 0xee23131a6d1c1ecb),
+    (4, 0xffffffffffffffff, 513, 0xe96f8cd11d6a79fb, 0xed9e1d3211f8ae9b),
+    (4, 0x0, 1000, 0x1c509680a949f478, 0x8872fac837bb9c5a),
+    (4, 0x1, 1023, 0x75e6788dff8e1f2b, 0x82c54f738e3ddf3b),
+    (4, 0x2a, 1024, 0x148c85d9d658279c, 0xa19c20b78c1c3289),
+    (4, 0xffffffffffffffff, 1025, 0x2c1151a9466e2858, 0x5b98892ead182404),
+    (4, 0x0, 2047, 0x0a268e49bad2b899, 0x83c27e4e42e59f7c),
+    (4, 0x1, 2048, 0xaf737632e1129e1e, 0x83f4ee66dda0879a),
+    (4, 0x2a, 2049, 0x35c9dc1a3a81f0b0, 0xe6bd129d60e01a07),
+    (4, 0xffffffffffffffff, 4095, 0x47e88cef31f1072d, 0x77af595b814fa89a),
+    (4, 0x0, 4096, 0x943c148d61cd3240, 0xe1978e36f5efd618),
+    (4, 0x1, 4097, 0x7673476b7643930a, 0xcf370b8b250555e9),
+    (4, 0x2a, 5000, 0x133a3083598168ae, 0x859ea10958131cc9),
+    (4, 0xffffffffffffffff, 8191, 0xe5afd6e9923c7b77, 0xf076285ae2d2f5b4),
+    (4, 0x0, 8192, 0xb9149d62fa86bcaa, 0x45c25ea2543c96ae),
+    (4, 0x1, 8193, 0xbd68edd095a7632a, 0xb57303293f63ced1),
+];
+
+#[cfg(test)]
+mod tests {
+    use crate::test_vectors::{input, vectors, verify_test_vectors, VERSION};
+    use crate::Params;
+
+    #[test]
+    fn test_verify() {
+        assert_eq!(VERSION, 1);
+        assert_eq!(verify_test_vectors(), Ok(()));
+    }
+
+    #[test]
+    fn test_coverage() {
+        assert_eq!(input(0), Vec::<u8>::new());
+        assert_eq!(input(9).len(), 9);
+        // Each length has its own input bytes.
+        assert_ne!(input(8), input(16)[..8]);
+
+        for len in [0, 8, 9, 16, 17, 255, 256, 257, 4095, 4096, 4097, 8193] {
+            assert!(vectors().any(|vector| vector.len == len));
+        }
+
+        for seed in [0, 1, 42, u64::MAX] {
+            assert!(vectors().any(|vector| vector.seed == seed));
+        }
+
+        let vector = vectors().find(|vector| vector.key.len() > 32).unwrap();
+        let params = Params::derive(vector.bits, vector.key);
+        assert_eq!(
+            params
+                .fingerprinter(vector.seed)
+                .write(&vector.input())
+                .digest(),
+            vector.fingerprint()
+        );
+    }
+}
@@ -0,0 +1,329 @@
+//! A static xor filter (Graf and Lemire, "Xor Filters: Faster and
+//! Smaller Than Bloom and Cuckoo Filters") for sets of items keyed by
+//! UMASH [`Fingerprint`]s.
+//!
+//! An [`XorFilter`] is built once from the full set of items, and
+//! then only answers membership queries, with a false positive rate
+//! of about `2^-16` in less than 20 bits per item.  Each item is
+//! [`Params::fingerprint`]ed, and the 128-bit [`Fingerprint`] is
+//! mixed with the filter's construction seed to pick three slots and
+//! a 16-bit tag: the item is in the filter if the xor of its three
+//! slots matches its tag.
+//!
+//! Filters serialise with the [`ParamsId`](crate::ParamsId) of their
+//! [`Params`], so a filter built offline can be queried by another
+//! process that derives the same [`Params`] (e.g., with
+//! [`Params::derive`]).  Compute fingerprints of raw bytes and call
+//! [`XorFilter::from_fingerprints`] and
+//! [`XorFilter::contains_fingerprint`] when filters are shared across
+//! programs (see the [crate docs](crate#data-structures)).
+use crate::codec::{Decoder, Encoder};
+use crate::range::hash_to_range;
+use crate::{DecodeError, Fingerprint, Params};
+use std::borrow::Borrow;
+
+const MAGIC: &[u8; 4] = b"UMXF";
+const VERSION: u8 = 1;
+
+// Construction fails with probability less than 1% for each seed.
+const MAX_ATTEMPTS: u64 = 100;
+
+/// Murmur3's 64-bit finaliser.
+#[inline(always)]
+fn fmix64(mut x: u64) -> u64 {
+    x ^= x >> 33;
+    x = x.wrapping_mul(0xff51afd7ed558ccd);
+    x ^= x >> 33;
+    x = x.wrapping_mul(0xc4ceb9fe1a85ec53);
+    x ^ (x >> 33)
+}
+
+/// Mixes all 128 bits of `fprint` with `seed`.
+#[inline(always)]
+fn mix(fprint: Fingerprint, seed: u64) -> u64 {
+    fmix64(fprint.hash() ^ fmix64(fprint.secondary().wrapping_add(seed)))
+}
+
+#[inline(always)]
+fn tag(hash: u64) -> u16 {
+    (hash ^ (hash >> 32)) as u16
+}
+
+/// An [`XorFilter`] is an immutable approximate set: it never reports
+/// false negatives, and reports false positives with probability
+/// `2^-16`.
+///
+/// See the [crate docs](crate#data-structures) for the choice of
+/// `P`.
+#[derive(Clone)]
+pub struct XorFilter<P: Borrow<Params>> {
+    params: P,
+    seed: u64,
+    len: u64,
+    block_length: u32,
+    // Three blocks of `block_length` tags.
+    slots: Vec<u16>,
+}
+
+#[inline(always)]
+fn positions(hash: u64, block_length: u32) -> [usize; 3] {
+    let n = block_length as u64;
+
+    [
+        hash_to_range(hash, n) as usize,
+        (n + hash_to_range(hash.rotate_left(21), n)) as usize,
+        (2 * n + hash_to_range(hash.rotate_left(42), n)) as usize,
+    ]
+}
+
+impl<P: Borrow<Params>> XorFilter<P> {
+    /// Builds an [`XorFilter`] for `items`.  Duplicate items are
+    /// ignored.
+    pub fn new<I, T>(params: P, items: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: std::hash::Hash,
+    {
+        let fingerprints: Vec<Fingerprint> = items
+            .into_iter()
+            .map(|item| params.borrow().fingerprint(item))
+            .collect();
+
+        Self::from_fingerprints(params, fingerprints)
+    }
+
+    /// Builds an [`XorFilter`] for the items with [`Fingerprint`]s
+    /// `fingerprints`.  The fingerprints must have been computed with
+    /// `params`; duplicates are ignored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if construction fails for 100 different seeds in a row,
+    /// which should never happen.
+    pub fn from_fingerprints<I>(params: P, fingerprints: I) -> Self
+    where
+        I: IntoIterator<Item = Fingerprint>,
+    {
+        let mut keys: Vec<Fingerprint> = fingerprints.into_iter().collect();
+
+        keys.sort_unstable();
+        keys.dedup();
+
+        let block_length = ((32.0 + 1.23 * keys.len() as f64) / 3.0).ceil() as u32;
+        let capacity = 3 * block_length as usize;
+        for attempt in 0..MAX_ATTEMPTS {
+            let seed = fmix64(attempt);
+            let hashes: Vec<u64> = keys.iter().map(|key| mix(*key, seed)).collect();
+
+            // For each slot, the number of keys that map to it, and the
+            // xor of their indices in `hashes`.
+            let mut counts = vec![0u32; capacity];
+            let mut xors = vec![0usize; capacity];
+            for (index, hash) in hashes.iter().enumerate() {
+                for slot in positions(*hash, block_length) {
+                    counts[slot] += 1;
+                    xors[slot] ^= index;
+                }
+            }
+
+            // Peel keys that are alone in one of their slots.
+            let mut queue: Vec<usize> = (0..capacity).filter(|&slot| counts[slot] == 1).collect();
+            let mut stack = Vec::with_capacity(keys.len());
+            while let Some(slot) = queue.pop() {
+                if counts[slot] != 1 {
+                    continue;
+                }
+
+                let index = xors[slot];
+                stack.push((index, slot));
+                for other in positions(hashes[index], block_length) {
+                    counts[other] -= 1;
+                    xors[other] ^= index;
+                    if counts[other] == 1 {
+                        queue.push(other);
+                    }
+                }
+            }
+
+            if stack.len() != keys.len() {
+                continue;
+            }
+
+            // Assign slots in reverse peeling order: each key's peeled
+            // slot is the last one of its three to be assigned.
+            let mut slots = vec![0u16; capacity];
+            for (index, slot) in stack.into_iter().rev() {
+                let hash = hashes[index];
+                let [a, b, c] = positions(hash, block_length);
+
+                slots[slot] = 0;
+                slots[slot] = tag(hash) ^ slots[a] ^ slots[b] ^ slots[c];
+            }
+
+            return XorFilter {
+                params,
+                seed,
+                len: keys.len() as u64,
+                block_length,
+                slots,
+            };
+        }
+
+        panic!("failed to build xor filter after {} attempts", MAX_ATTEMPTS);
+    }
+
+    /// Returns the [`Params`] for this filter.
+    #[inline(always)]
+    pub fn params(&self) -> &Params {
+        self.params.borrow()
+    }
+
+    /// Returns the number of distinct items in the filter.
+    #[inline(always)]
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns whether the filter was built without any item.
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of bits in the filter, per item.
+    pub fn bits_per_item(&self) -> f64 {
+        16.0 * self.slots.len() as f64 / self.len.max(1) as f64
+    }
+
+    /// Determines whether `item` is probably in the filter.
+    pub fn contains<T: std::hash::Hash + ?Sized>(&self, item: &T) -> bool {
+        self.contains_fingerprint(self.params().fingerprint(item))
+    }
+
+    /// Determines whether the item with [`Fingerprint`] `fprint` is
+    /// probably in the filter.  The fingerprint must have been
+    /// computed with this filter's [`Params`].
+    pub fn contains_fingerprint(&self, fprint: Fingerprint) -> bool {
+        let hash = mix(fprint, self.seed);
+        let [a, b, c] = positions(hash, self.block_length);
+
+        tag(hash) == self.slots[a] ^ self.slots[b] ^ self.slots[c]
+    }
+
+    /// Serialises the filter to bytes, including the
+    /// [`ParamsId`](crate::ParamsId) of the filter's [`Params`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut encoder = Encoder::new(MAGIC, VERSION, self.params().id());
+
+        encoder.u64(self.seed).u64(self.len).u32(self.block_length);
+        for slot in self.slots.iter() {
+            encoder.u16(*slot);
+        }
+
+        encoder.finish()
+    }
+
+    /// Deserialises a filter serialised by [`XorFilter::to_bytes`].
+    ///
+    /// Fails if `params` is not the [`Params`] that built the filter.
+    pub fn from_bytes(params: P, bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut decoder = Decoder::new(bytes, MAGIC, VERSION, params.borrow().id())?;
+        let seed = decoder.u64()?;
+        let len = decoder.u64()?;
+        let block_length = decoder.u32()?;
+
+        if block_length == 0 {
+            return Err(DecodeError::Invalid("empty xor filter"));
+        }
+
+        let capacity = 3 * block_length as u64;
+        if decoder.remaining() as u64 != 2 * capacity || len > capacity {
+            return Err(DecodeError::Invalid("xor filter size mismatch"));
+        }
+
+        let slots = (0..capacity)
+            .map(|_| decoder.u16())
+            .collect::<Result<Vec<_>, _>>()?;
+        decoder.finish()?;
+
+        Ok(XorFilter {
+            params,
+            seed,
+            len,
+            block_length,
+            slots,
+        })
+    }
+}
+
+/// See the [crate docs](crate#data-structures) for the
+/// [`Debug`](std::fmt::Debug) representation of data structures.
+impl<P: Borrow<Params>> std::fmt::Debug for XorFilter<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("XorFilter")
+            .field("params", &self.params().id())
+            .field("len", &self.len)
+            .field("num_slots", &self.slots.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::xor_filter::XorFilter;
+    use crate::{DecodeError, Params};
+
+    #[test]
+    fn test_membership() {
+        let params = Params::derive(0, b"xor filter");
+        let filter = XorFilter::new(&params, (0..10_000u32).chain(0..100));
+
+        assert_eq!(filter.len(), 10_000);
+        assert!(filter.bits_per_item() < 20.0);
+        for i in 0..10_000u32 {
+            assert!(filter.contains(&i));
+        }
+
+        let false_positives = (10_000..110_000u32).filter(|i| filter.contains(i)).count();
+        // We expect ~1.5 false positives.
+        assert!(false_positives < 10, "{} false positives", false_positives);
+
+        let empty = XorFilter::new(&params, Vec::<u32>::new());
+        assert!(empty.is_empty());
+        assert!((0..1000u32).filter(|i| empty.contains(i)).count() < 5);
+    }
+
+    #[test]
+    fn test_bytes() {
+        let params = Params::derive(0, b"xor filter");
+        let items = ["foo", "bar", "baz"];
+        let fingerprints: Vec<_> = items
+            .iter()
+            .map(|item| params.fingerprinter(0).write(item.as_bytes()).digest())
+            .collect();
+        let filter = XorFilter::from_fingerprints(&params, fingerprints.clone());
+        let bytes = filter.to_bytes();
+
+        // Another process derives the same params, and queries the filter.
+        let derived = Params::derive(0, b"xor filter");
+        let copy = XorFilter::from_bytes(&derived, &bytes).expect("valid encoding");
+        assert_eq!(copy.len(), 3);
+        for fprint in fingerprints {
+            assert!(copy.contains_fingerprint(fprint));
+        }
+
+        assert_eq!(
+            XorFilter::from_bytes(&params, &bytes[..bytes.len() - 1]).err(),
+            Some(DecodeError::Invalid("xor filter size mismatch"))
+        );
+
+        let other = Params::derive(1, b"xor filter");
+        assert_eq!(
+            XorFilter::from_bytes(&other, &bytes).err(),
+            Some(DecodeError::ParamsMismatch {
+                expected: other.id(),
+                found: params.id()
+            })
+        );
+    }
+}
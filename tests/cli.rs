@@ -0,0 +1,61 @@
+//! Runs the `umash` command-line tool.
+use std::path::Path;
+use std::process::{Command, Output};
+
+fn umash(args: &[&str], dir: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_umash"))
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("must run umash")
+}
+
+#[test]
+fn test_dupes() {
+    let dir = std::env::temp_dir().join(format!("umash-cli-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("tree/sub")).expect("must create dirs");
+
+    std::fs::write(dir.join("tree/a"), b"hello").expect("must write");
+    std::fs::write(dir.join("tree/sub/b"), b"hello").expect("must write");
+    std::fs::write(dir.join("tree/unique"), b"world!").expect("must write");
+
+    let output = umash(&["dupes", "tree"], &dir);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).expect("must be utf-8");
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("5 bytes, fingerprint "));
+    assert_eq!(
+        &lines[1..],
+        &[
+            Path::new("tree").join("a").to_str().unwrap(),
+            Path::new("tree").join("sub").join("b").to_str().unwrap()
+        ]
+    );
+
+    let output = umash(&["dupes", "--json", "tree"], &dir);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).expect("must be utf-8");
+    assert!(stdout.starts_with("[{\"size\":5,\"fingerprint\":\""));
+    assert!(stdout
+        .trim_end()
+        .ends_with("\"paths\":[\"tree/a\",\"tree/sub/b\"]}]"));
+
+    // Unreadable paths are reported, but don't hide the results.
+    let output = umash(&["dupes", "missing", "tree"], &dir);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&output.stdout).lines().count(), 3);
+    let stderr = String::from_utf8(output.stderr).expect("must be utf-8");
+    assert!(stderr.starts_with("umash: missing: "), "{}", stderr);
+    assert!(stderr.ends_with("umash: could not read 1 path(s)\n"));
+
+    let output = umash(&["dupes"], &dir);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "umash: usage: umash dupes [--json] <paths...>\n"
+    );
+
+    let _ = std::fs::remove_dir_all(&dir);
+}